@@ -3,10 +3,12 @@ use std::env;
 pub fn main() {
     let using_web_sys = cfg!(feature = "web_sys");
     let using_std_web = cfg!(feature = "std_web");
+    // Without the service, nothing touches the browser, so no web feature is needed.
+    let using_service = cfg!(feature = "service");
     if using_web_sys && using_std_web {
         panic!("Yew does not allow the `web_sys` and `std_web` cargo features to be used simultaneously");
-    } else if !using_web_sys && !using_std_web {
-        panic!("Yew requires selecting either the `web_sys` or `std_web` cargo feature");
+    } else if using_service && !using_web_sys && !using_std_web {
+        panic!("Yew requires selecting either the `web_sys` or `std_web` cargo feature when the `service` feature (or one that depends on it) is enabled");
     }
 
     let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
@@ -2,3 +2,7 @@
 set -euxo pipefail # https://vaneyckt.io/posts/safer_bash_scripts_with_set_euxo_pipefail/
 
 cargo test --all
+# The routing core, without the service, builds natively without a web feature.
+cargo test -p yew-router --no-default-features
+# Tests that render `Html` or create a `RouteService` only link natively with `web_sys`.
+cargo test -p yew-router --no-default-features --features core,web_sys,doc_test --lib
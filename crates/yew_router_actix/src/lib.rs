@@ -0,0 +1,177 @@
+//! Actix-web guards and extractors for routes defined with a `Switch` type.
+//!
+//! This allows a server to route requests with the same typed route definition as a Yew app,
+//! instead of repeating each route as a path pattern.
+//!
+//! # Example
+//! ```no_run
+//! use actix_web::{web, App, HttpResponse, HttpServer};
+//! use yew_router::Switch;
+//! use yew_router_actix::Switched;
+//!
+//! #[derive(Switch, Clone)]
+//! pub enum ApiRoute {
+//!     #[to = "/api/hello/{name}"]
+//!     Hello { name: String },
+//! }
+//!
+//! async fn api(route: Switched<ApiRoute>) -> HttpResponse {
+//!     match route.into_inner() {
+//!         ApiRoute::Hello { name } => HttpResponse::Ok().body(name),
+//!     }
+//! }
+//!
+//! #[actix_rt::main]
+//! async fn main() -> std::io::Result<()> {
+//!     HttpServer::new(|| {
+//!         App::new().default_service(
+//!             web::route()
+//!                 .guard(yew_router_actix::guard::<ApiRoute>())
+//!                 .to(api),
+//!         )
+//!     })
+//!     .bind(("0.0.0.0", 8000))?
+//!     .run()
+//!     .await
+//! }
+//! ```
+#![deny(
+    missing_docs,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unstable_features,
+    unused_qualifications
+)]
+
+use actix_web::{
+    dev::{Payload, RequestHead},
+    error::ErrorNotFound,
+    guard::Guard,
+    http::Uri,
+    Error, FromRequest, HttpRequest,
+};
+use futures::future::{ready, Ready};
+use std::{fmt, marker::PhantomData, ops::Deref};
+use yew_router::{route::Route, Switch};
+
+/// Gets the route of a request's uri, which is its path followed by its query, if it has one.
+pub fn route(uri: &Uri) -> String {
+    match uri.query() {
+        Some(query) => format!("{}?{}", uri.path(), query),
+        None => uri.path().to_string(),
+    }
+}
+
+/// Switches the route of a request's uri.
+fn switch<SW: Switch>(uri: &Uri) -> Option<SW> {
+    SW::switch(Route::new_no_state(route(uri)))
+}
+
+/// A guard that only matches requests with routes that `SW` matches.
+pub struct SwitchGuard<SW> {
+    phantom_data: PhantomData<fn() -> SW>,
+}
+
+impl<SW> fmt::Debug for SwitchGuard<SW> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SwitchGuard").finish()
+    }
+}
+
+/// Creates a guard that only matches requests with routes that `SW` matches.
+pub fn guard<SW: Switch>() -> SwitchGuard<SW> {
+    SwitchGuard {
+        phantom_data: PhantomData,
+    }
+}
+
+impl<SW: Switch> Guard for SwitchGuard<SW> {
+    fn check(&self, request: &RequestHead) -> bool {
+        switch::<SW>(&request.uri).is_some()
+    }
+}
+
+/// Extracts the item switched from the route of a request.
+///
+/// Requests with routes that `SW` doesn't match fail with a `404 Not Found`.
+/// Use `Option<Switched<SW>>` to handle them within the handler instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Switched<SW>(pub SW);
+
+impl<SW> Switched<SW> {
+    /// Gets the item that was switched from the route.
+    pub fn into_inner(self) -> SW {
+        self.0
+    }
+}
+
+impl<SW> Deref for Switched<SW> {
+    type Target = SW;
+
+    fn deref(&self) -> &SW {
+        &self.0
+    }
+}
+
+impl<SW: Switch> FromRequest for Switched<SW> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            switch(req.uri())
+                .map(Switched)
+                .ok_or_else(|| ErrorNotFound("the route didn't match any variant")),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test::TestRequest};
+
+    #[derive(Switch, Debug, Clone, PartialEq)]
+    pub enum ApiRoute {
+        #[to = "/api/hello/{name}?greeting={greeting}"]
+        Greet { name: String, greeting: String },
+        #[to = "/api/hello/{name}"]
+        Hello { name: String },
+    }
+
+    #[test]
+    fn guard_checks_route() {
+        let guard = guard::<ApiRoute>();
+        let request = TestRequest::with_uri("/api/hello/world").to_http_request();
+        assert!(guard.check(request.head()));
+        let request = TestRequest::with_uri("/api/goodbye/world").to_http_request();
+        assert!(!guard.check(request.head()));
+    }
+
+    #[actix_rt::test]
+    async fn extracts_switched_item() {
+        let request = TestRequest::with_uri("/api/hello/world?greeting=hi").to_http_request();
+        let switched = Switched::<ApiRoute>::extract(&request).await.unwrap();
+        assert_eq!(
+            switched.into_inner(),
+            ApiRoute::Greet {
+                name: "world".to_string(),
+                greeting: "hi".to_string()
+            }
+        );
+    }
+
+    #[actix_rt::test]
+    async fn unmatched_route_not_found() {
+        let request = TestRequest::with_uri("/api/goodbye/world").to_http_request();
+        let error = Switched::<ApiRoute>::extract(&request).await.unwrap_err();
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -26,6 +26,37 @@ mod switch;
 /// _everything_, and the next 3 path sections respectively.
 /// `{1:field_name}` is the same as `{field_name}`.
 ///
+/// Single section captures can be constrained, so that the matcher will reject sections that don't
+/// satisfy the constraint, moving on to the next variant.
+/// `{id:u32}` will only match sections that can be parsed as a `u32`,
+/// and `{id|[a-z0-9_]+}` will only match sections consisting of characters within the class.
+/// Integer, float, `bool`, and `char` types can be used as constraints.
+///
+/// Query parameters are matched regardless of the order they appear in,
+/// so `#[to = "/search?q={query}&page={page}"]` will match "/search?page=2&q=yew".
+/// Parameters that aren't mentioned in the route matcher string are ignored.
+/// A parameter can be made optional with a trailing `?` - `?q={query}?`, which works well with an `Option` field,
+/// or be given a default that is used when it is missing - `?page={page=1}`.
+/// If a parameter is repeated, like "/posts?tag=yew&tag=rust", every value can be collected into a `Vec` field.
+/// Otherwise, only the first value is used.
+/// The parameter is optional for a `Vec` field, which is empty if the key is absent.
+/// `Vec` fields can only be captured by query parameters.
+///
+/// Captured values are percent-decoded before they are parsed into fields,
+/// and are percent-encoded when building a route, so `"hello world"` is written as "hello%20world".
+/// Values captured by query parameters also have each `+` decoded to a space, like in forms,
+/// so "?q=hello+world" captures `"hello world"`.
+///
+/// Alternative spellings of a literal can be matched with `(x|y)`.
+/// `#[to = "/(posts|blog)/{id}"]` will match both "/posts/1" and "/blog/1".
+/// When building a route, the first alternative is used.
+///
+/// Sections of the path can be made optional by surrounding them with `[]`.
+/// `#[to = "/users[/{id}]"]` will match both "/users" and "/users/42",
+/// and if the field that the capture populates is an `Option`, it will be `None` when the section is absent.
+/// When building a route, an optional section is only written if the `Option` fields captured within it are `Some`.
+/// Optional sections can be nested, but they can't start with a capture, or be directly followed by one.
+///
 /// Tuple-structs and Tuple-enum-variants are also supported.
 /// If you don't want to specify keys that don't correspond to any specific field,
 /// `{}`, `{*}`, and `{4}` also denote valid capture sections when used on structs and variants without named fields.
@@ -42,6 +73,49 @@ mod switch;
 /// any characters are left after matching the route matcher string, the match should fail.
 /// This means that `[to = "/!"]` will match "/" and _only_ "/".
 ///
+/// To avoid trying every variant in turn, the derived implementation first finds the variants whose route matcher strings
+/// could match the route, using a single prefix tree over the literals and captures of every variant,
+/// and only tries those.
+/// The tree follows each route matcher string up to its first optional section, alternation, query or `!`.
+///
+/// Adding `#[switch(rank = "specificity")]` to an enum will instead try variants from most to least specific,
+/// so that the order the variants are declared in doesn't matter.
+/// Variants that match more literal sections are tried first, followed by those with more captures,
+/// more constrained captures, fewer `{*}` captures, and finally those that end with a `!`.
+/// Variants that are equally specific are tried in the order they are declared in.
+///
+/// Adding `#[switch(trailing_slash = "ignore")]` makes every variant match regardless of whether the
+/// path of the route ends with a `/`, so `/about` and `/about/` are treated the same.
+/// `#[switch(trailing_slash = "redirect")]` does the same, but also makes the `Router` replace
+/// the route with the one built from the matched item, if they only differ by the trailing `/`.
+/// The default, `"strict"`, requires the route to end with a `/` only if the route matcher string does.
+///
+/// Literals in derived route matchers are matched without regard to case, unlike those of a
+/// `RouteMatcher` created with `try_from`, which are case sensitive.
+/// Adding `#[switch(case_sensitive)]` to a struct or enum makes its literals case sensitive,
+/// and adding `#[switch(case_sensitive)]` or `#[switch(case_insensitive)]` to a variant overrides
+/// the setting of the enum for that variant alone. Captured values always keep their case.
+///
+/// Adding `#[switch(deny_unreachable)]` to an enum emits a compile error at every variant that can
+/// never be matched, because a variant above it will match every route that it would.
+/// Only variants that are certain to be unreachable are reported - if the variant above it has
+/// fields, creating them might fail, so the variant below might still be reached, unless every
+/// field is a `String` captured from literal characters of the variant below, like `{name}` in
+/// `/user/{name}` capturing `settings` from `/user/settings`.
+///
+/// If a route matcher string can't be parsed, a compile error pointing at the string is emitted,
+/// describing where in the string the problem was encountered, and why.
+///
+/// To find out why a route isn't matched, `Switch::explain` reports, for each variant in the order
+/// they are tried, how much of the route its matcher matched and which token failed,
+/// or which field couldn't be created from what was captured for it.
+///
+/// `Switch::routes` describes the matcher of each variant - its literal sections, the names of its
+/// captures, and its query keys - and lists every route it matches if it has no captures.
+/// `Switch::static_routes` collects those routes, for generating a sitemap or pre-rendering pages.
+/// `switch::route_manifest` serializes the descriptions, along with each variant's matcher string
+/// and the types of the fields that its captures populate, as JSON for services in other languages.
+///
 /// -----
 /// There are other attributes as well.
 /// `#[rest]`, `#[rest="field_name"]` and `#[end]` attributes exist as well.
@@ -49,6 +123,8 @@ mod switch;
 /// `#[end]` is equivalent to `!`.
 /// The `#[rest]` attributes are good if you just want to delegate the whole matching of a variant to a specific
 /// wrapped struct or enum that also implements `Switch`.
+/// The derived implementation matches against slices of the route (`Switch::from_route_part_ref`),
+/// so delegating to nested implementors doesn't copy the route.
 ///
 /// ------
 /// # Example
@@ -77,7 +153,32 @@ mod switch;
 /// }
 /// ```
 /// Check out the examples directory in the repository to see some more usages of the routing syntax.
-#[proc_macro_derive(Switch, attributes(to, rest, end))]
+///
+/// A malformed route matcher string will fail to compile:
+/// ```compile_fail
+/// use yew_router::Switch;
+///
+/// #[derive(Switch, Clone)]
+/// enum AppRoute {
+///     #[to = "/unclosed/{capture"]
+///     Unclosed { capture: String },
+/// }
+/// ```
+///
+/// As will a variant that is shadowed by one above it, if unreachable variants are denied:
+/// ```compile_fail
+/// use yew_router::Switch;
+///
+/// #[derive(Switch, Clone)]
+/// #[switch(deny_unreachable)]
+/// enum AppRoute {
+///     #[to = "/user"]
+///     User,
+///     #[to = "/user/settings"]
+///     Settings,
+/// }
+/// ```
+#[proc_macro_derive(Switch, attributes(to, rest, end, switch))]
 pub fn switch(tokens: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(tokens as DeriveInput);
 
@@ -1,47 +1,83 @@
-use crate::switch::shadow::{ShadowCaptureVariant, ShadowMatcherToken};
+use crate::switch::shadow::{ShadowCaptureVariant, ShadowMatcherToken, ShadowQueryValue};
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{Data, DeriveInput, Fields, Ident, Variant};
+use std::collections::{HashMap, HashSet};
+use syn::{Data, DeriveInput, Field, Fields, GenericArgument, Ident, PathArguments, Type, Variant};
 
 mod attribute;
 mod enum_impl;
+mod explain;
+mod reachability;
+mod routes;
 mod shadow;
+mod specificity;
 mod struct_impl;
 mod switch_impl;
 
-use self::{attribute::AttrToken, switch_impl::SwitchImpl};
+use self::{
+    attribute::{AttrToken, ContainerAttributes, MatcherSettings, Rank},
+    explain::Explain,
+    routes::Routes,
+    switch_impl::SwitchImpl,
+};
 use crate::switch::{enum_impl::EnumInner, struct_impl::StructInner};
 use yew_router_route_parser::FieldNamingScheme;
 
 /// Holds data that is required to derive Switch for a struct or a single enum variant.
 pub struct SwitchItem {
     pub matcher: Vec<ShadowMatcherToken>,
+    /// The route matcher string that the matcher was parsed from.
+    pub matcher_string: String,
     pub ident: Ident,
     pub fields: Fields,
+    pub settings: MatcherSettings,
 }
 
 pub fn switch_impl(input: DeriveInput) -> syn::Result<TokenStream> {
     let ident: Ident = input.ident;
     let generics = input.generics;
+    let container_attributes = ContainerAttributes::from_attributes(&input.attrs)?;
 
     Ok(match input.data {
         Data::Struct(ds) => {
+            if container_attributes.rank != Rank::Declaration {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "ranking variants is only supported for enums",
+                ));
+            }
+            if container_attributes.deny_unreachable {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "checking for unreachable variants is only supported for enums",
+                ));
+            }
             let field_naming_scheme = match ds.fields {
                 Fields::Unnamed(_) => FieldNamingScheme::Unnamed,
                 Fields::Unit => FieldNamingScheme::Unit,
                 Fields::Named(_) => FieldNamingScheme::Named,
             };
-            let matcher = AttrToken::convert_attributes_to_tokens(input.attrs)?
+            let attr_tokens = AttrToken::convert_attributes_to_tokens(input.attrs)?;
+            let matcher_string = AttrToken::matcher_string(&attr_tokens);
+            let mut matcher = attr_tokens
                 .into_iter()
                 .enumerate()
                 .map(|(index, at)| at.into_shadow_matcher_tokens(index, field_naming_scheme))
+                .collect::<syn::Result<Vec<_>>>()?
+                .into_iter()
                 .flatten()
                 .collect::<Vec<_>>();
+            if let FieldNamingScheme::Unnamed = field_naming_scheme {
+                number_captures(&mut matcher, &mut 0);
+            }
+            prepare_vec_captures(&mut matcher, &ds.fields)?;
 
             let item = SwitchItem {
                 matcher,
+                matcher_string,
                 ident: ident.clone(), // TODO make SwitchItem take references instead.
                 fields: ds.fields,
+                settings: container_attributes.matcher_settings,
             };
 
             SwitchImpl {
@@ -53,12 +89,19 @@ pub fn switch_impl(input: DeriveInput) -> syn::Result<TokenStream> {
                         switch_item: &item,
                         item: &Ident::new("self", Span::call_site()),
                     },
+                    explain: Explain {
+                        switch_items: std::slice::from_ref(&item),
+                    },
+                    routes: Routes {
+                        switch_items: std::slice::from_ref(&item),
+                    },
+                    trailing_slash: container_attributes.matcher_settings.trailing_slash,
                 },
             }
             .to_token_stream()
         }
         Data::Enum(de) => {
-            let switch_variants = de
+            let mut switch_variants = de
                 .variants
                 .into_iter()
                 .map(|variant: Variant| {
@@ -67,20 +110,38 @@ pub fn switch_impl(input: DeriveInput) -> syn::Result<TokenStream> {
                         Fields::Unit => FieldNamingScheme::Unit,
                         Fields::Named(_) => yew_router_route_parser::FieldNamingScheme::Named,
                     };
-                    let matcher = AttrToken::convert_attributes_to_tokens(variant.attrs)?
+                    let settings = container_attributes
+                        .matcher_settings
+                        .for_variant(&variant.attrs)?;
+                    let attr_tokens = AttrToken::convert_attributes_to_tokens(variant.attrs)?;
+                    let matcher_string = AttrToken::matcher_string(&attr_tokens);
+                    let mut matcher = attr_tokens
                         .into_iter()
                         .enumerate()
                         .map(|(index, at)| at.into_shadow_matcher_tokens(index, field_type))
+                        .collect::<syn::Result<Vec<_>>>()?
+                        .into_iter()
                         .flatten()
                         .collect::<Vec<_>>();
+                    if let FieldNamingScheme::Unnamed = field_type {
+                        number_captures(&mut matcher, &mut 0);
+                    }
+                    prepare_vec_captures(&mut matcher, &variant.fields)?;
                     Ok(SwitchItem {
                         matcher,
+                        matcher_string,
                         ident: variant.ident,
                         fields: variant.fields,
+                        settings,
                     })
                 })
                 .collect::<syn::Result<Vec<_>>>()?;
-
+            if container_attributes.rank == Rank::Specificity {
+                specificity::sort_by_specificity(&mut switch_variants);
+            }
+            if container_attributes.deny_unreachable {
+                reachability::check_reachability(&switch_variants)?;
+            }
 
             SwitchImpl {
                 target_ident: &ident,
@@ -95,6 +156,13 @@ pub fn switch_impl(input: DeriveInput) -> syn::Result<TokenStream> {
                         enum_ident: &ident,
                         match_item: &Ident::new("self", Span::call_site()),
                     },
+                    explain: Explain {
+                        switch_items: &switch_variants,
+                    },
+                    routes: Routes {
+                        switch_items: &switch_variants,
+                    },
+                    trailing_slash: container_attributes.matcher_settings.trailing_slash,
                 },
             }
             .to_token_stream()
@@ -119,60 +187,517 @@ impl<T> Flatten<T> for Option<Option<T>> {
     }
 }
 
-fn build_matcher_from_tokens(tokens: &[ShadowMatcherToken]) -> TokenStream {
+/// The matcher is a `static`, so the tokens are only created once, and don't need to allocate.
+fn build_matcher_from_tokens(
+    tokens: &[ShadowMatcherToken],
+    settings: &MatcherSettings,
+) -> TokenStream {
     quote! {
-        let settings = ::yew_router::matcher::MatcherSettings {
-            case_insensitive: true,
-        };
-        let matcher = ::yew_router::matcher::RouteMatcher {
-            tokens: ::std::vec![#(#tokens),*],
-            settings
+        let matcher: &'static ::yew_router::matcher::RouteMatcher = {
+            static MATCHER: ::yew_router::matcher::RouteMatcher = ::yew_router::matcher::RouteMatcher {
+                tokens: ::std::borrow::Cow::Borrowed(&[#(#tokens),*]),
+                settings: #settings,
+            };
+            &MATCHER
         };
     }
 }
 
-/// Enum indicating which sort of writer is needed.
-pub(crate) enum FieldType {
-    Named,
-    Unnamed { index: usize },
-    Unit,
+/// Names each capture in the matcher of a tuple struct or variant after the index of the field
+/// it populates.
+///
+/// This allows the captures to be looked up by name, so a capture within an optional section
+/// being absent won't cause the captures following it to populate the wrong fields.
+fn number_captures(tokens: &mut [ShadowMatcherToken], next_index: &mut usize) {
+    for token in tokens {
+        match token {
+            ShadowMatcherToken::Capture(capture) => {
+                let unnamed = std::mem::replace(capture, ShadowCaptureVariant::Unnamed);
+                *capture = unnamed.rename(next_index.to_string());
+                *next_index += 1;
+            }
+            ShadowMatcherToken::Query(params) => {
+                for param in params {
+                    if let Some(capture) = param.value.capture_mut() {
+                        let unnamed = std::mem::replace(capture, ShadowCaptureVariant::Unnamed);
+                        *capture = unnamed.rename(next_index.to_string());
+                        *next_index += 1;
+                    }
+                }
+            }
+            ShadowMatcherToken::Optional(tokens) => number_captures(tokens, next_index),
+            ShadowMatcherToken::Exact(_)
+            | ShadowMatcherToken::Alternation(_)
+            | ShadowMatcherToken::End => {}
+        }
+    }
 }
 
-/// This assumes that the variant/struct has been destructured.
-fn write_for_token(token: &ShadowMatcherToken, naming_scheme: FieldType) -> TokenStream {
-    match token {
-        ShadowMatcherToken::Exact(lit) => {
+/// Checks that `Vec` fields are only captured by query parameters, and makes those captures
+/// optional, so that an empty `Vec` is written as an absent key and is switched back from it.
+///
+/// This assumes that unnamed captures have already been numbered.
+fn prepare_vec_captures(tokens: &mut [ShadowMatcherToken], fields: &Fields) -> syn::Result<()> {
+    let vec_fields = fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| FieldKind::of(&field.ty) == FieldKind::Vec)
+        .map(|(index, field)| {
+            let name = match &field.ident {
+                Some(ident) => ident.to_string(),
+                None => index.to_string(),
+            };
+            (name, field)
+        })
+        .collect::<HashMap<_, _>>();
+    prepare_vec_captures_in(tokens, &vec_fields)
+}
+
+fn prepare_vec_captures_in(
+    tokens: &mut [ShadowMatcherToken],
+    vec_fields: &HashMap<String, &Field>,
+) -> syn::Result<()> {
+    for token in tokens {
+        match token {
+            ShadowMatcherToken::Capture(capture) => {
+                if let Some(field) = capture.name().and_then(|name| vec_fields.get(name)) {
+                    return Err(syn::Error::new_spanned(
+                        field,
+                        "`Vec` fields can only be captured by query parameters",
+                    ));
+                }
+            }
+            ShadowMatcherToken::Query(params) => {
+                for param in params {
+                    if let ShadowQueryValue::Capture(capture) = &mut param.value {
+                        if capture
+                            .name()
+                            .map_or(false, |name| vec_fields.contains_key(name))
+                        {
+                            let capture = std::mem::replace(capture, ShadowCaptureVariant::Unnamed);
+                            param.value = ShadowQueryValue::OptionalCapture(capture);
+                        }
+                    }
+                }
+            }
+            ShadowMatcherToken::Optional(tokens) => prepare_vec_captures_in(tokens, vec_fields)?,
+            ShadowMatcherToken::Exact(_)
+            | ShadowMatcherToken::Alternation(_)
+            | ShadowMatcherToken::End => {}
+        }
+    }
+    Ok(())
+}
+
+/// If the type is the given wrapper (eg. `Option`), get the type it wraps.
+///
+/// This is determined syntactically, so only `Wrapper<T>` and paths ending in `Wrapper<T>` are
+/// detected.
+fn wrapped_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            let segment = type_path.path.segments.last()?;
+            if segment.ident != wrapper {
+                return None;
+            }
+            match &segment.arguments {
+                PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+                    match args.args.first()? {
+                        GenericArgument::Type(inner) => Some(inner),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// How a field is populated from the values captured for it.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum FieldKind {
+    /// The field is created from the first value.
+    Single,
+    /// The field is `None` if no value was captured.
+    Option,
+    /// The field is created from every value of a repeated query parameter.
+    Vec,
+}
+
+impl FieldKind {
+    fn of(ty: &Type) -> Self {
+        if wrapped_type(ty, "Option").is_some() {
+            FieldKind::Option
+        } else if wrapped_type(ty, "Vec").is_some() {
+            FieldKind::Vec
+        } else {
+            FieldKind::Single
+        }
+    }
+}
+
+/// Creates an expression that populates a field from the captures stored under the key.
+///
+/// This assumes that `captures` (a `RefCaptures`) and `state` are in scope, and will return
+/// early if the field can't be created.
+/// `Option` fields will be `None` if the capture isn't present, which happens when the capture
+/// is in an optional section.
+/// `Vec` fields will contain every value captured for the key, which may be none.
+/// Values captured by query parameters have each `+` decoded to a space first.
+fn build_field_from_capture(key: &str, field_ty: &Type, query: bool) -> TokenStream {
+    let decode = decode_query_value(query);
+    let from_route_part = |ty: &Type| {
+        quote! {
+            {
+                #decode
+                let (v, s) = <#ty as ::yew_router::Switch>::from_route_part_ref(
+                    value,
+                    state,
+                );
+                match v {
+                    ::std::option::Option::Some(val) => {
+                        state = s; // Set state for the next var.
+                        val
+                    },
+                    ::std::option::Option::None => return (::std::option::Option::None, s) // Failed
+                }
+            }
+        }
+    };
+    match FieldKind::of(field_ty) {
+        FieldKind::Option => {
+            let inner_ty = wrapped_type(field_ty, "Option").expect("Option field");
+            let item = from_route_part(inner_ty);
             quote! {
-                write!(buf, "{}", #lit).unwrap();
+                match captures.remove(#key).and_then(|values| values.into_iter().next()) {
+                    ::std::option::Option::Some(value) => ::std::option::Option::Some(#item),
+                    ::std::option::Option::None => ::std::option::Option::None,
+                }
             }
         }
-        ShadowMatcherToken::Capture(capture) => match naming_scheme {
-            FieldType::Named | FieldType::Unit => match &capture {
-                ShadowCaptureVariant::Named(name)
-                | ShadowCaptureVariant::ManyNamed(name)
-                | ShadowCaptureVariant::NumberedNamed { name, .. } => {
-                    let name = Ident::new(&name, Span::call_site());
-                    quote! {
-                        state = state.or_else(|| #name.build_route_section(buf));
+        FieldKind::Vec => {
+            let inner_ty = wrapped_type(field_ty, "Vec").expect("Vec field");
+            let item = from_route_part(inner_ty);
+            quote! {
+                {
+                    let values = captures.remove(#key).unwrap_or_default();
+                    let mut items = ::std::vec::Vec::with_capacity(values.len());
+                    for value in values {
+                        items.push(#item);
                     }
+                    items
                 }
-                ShadowCaptureVariant::Unnamed
-                | ShadowCaptureVariant::ManyUnnamed
-                | ShadowCaptureVariant::NumberedUnnamed { .. } => {
-                    panic!("Unnamed matcher sections not allowed for named field types")
+            }
+        }
+        FieldKind::Single => quote! {
+            {
+                let (v, s) = match captures.remove(#key).and_then(|values| values.into_iter().next()) {
+                    ::std::option::Option::Some(value) => {
+                        #decode
+                        <#field_ty as ::yew_router::Switch>::from_route_part_ref(
+                            value,
+                            state,
+                        )
+                    }
+                    ::std::option::Option::None => {
+                        (
+                            <#field_ty as ::yew_router::Switch>::key_not_available(),
+                            state,
+                        )
+                    }
+                };
+                match v {
+                    ::std::option::Option::Some(val) => {
+                        state = s; // Set state for the next var.
+                        val
+                    },
+                    ::std::option::Option::None => return (::std::option::Option::None, s) // Failed
                 }
-            },
-            FieldType::Unnamed { index } => {
-                let name = unnamed_field_index_item(index);
+            }
+        },
+    }
+}
+
+/// Creates statements that rebind `value` with each `+` replaced by a space, if it was captured
+/// by a query parameter, where `+` encodes a space in forms.
+pub(crate) fn decode_query_value(query: bool) -> TokenStream {
+    if query {
+        quote! {
+            let __decoded = ::yew_router::switch::decode_query_value(value);
+            let value: &str = &__decoded;
+        }
+    } else {
+        quote! {}
+    }
+}
+
+/// Gets the names of the captures within query parameters.
+pub(crate) fn query_captures(tokens: &[ShadowMatcherToken]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for token in tokens {
+        match token {
+            ShadowMatcherToken::Query(params) => {
+                names.extend(params.iter().filter_map(|param| match &param.value {
+                    ShadowQueryValue::Exact(_) => None,
+                    ShadowQueryValue::Capture(capture)
+                    | ShadowQueryValue::OptionalCapture(capture)
+                    | ShadowQueryValue::DefaultedCapture { capture, .. } => {
+                        capture.name().map(ToString::to_string)
+                    }
+                }))
+            }
+            ShadowMatcherToken::Optional(tokens) => names.extend(query_captures(tokens)),
+            ShadowMatcherToken::Exact(_)
+            | ShadowMatcherToken::Alternation(_)
+            | ShadowMatcherToken::Capture(_)
+            | ShadowMatcherToken::End => {}
+        }
+    }
+    names
+}
+
+/// The field that a capture is written from when building a route.
+pub(crate) struct CaptureField {
+    ident: Ident,
+    kind: FieldKind,
+}
+
+/// Associates the names of captures with the fields they correspond to.
+///
+/// This assumes that the fields will be destructured using their names, or in the case of
+/// unnamed fields, `unnamed_field_index_item`.
+pub(crate) fn capture_fields(fields: &Fields) -> HashMap<String, CaptureField> {
+    match fields {
+        Fields::Named(fields_named) => fields_named
+            .named
+            .iter()
+            .filter_map(|field| {
+                field.ident.as_ref().map(|ident| {
+                    let capture_field = CaptureField {
+                        ident: ident.clone(),
+                        kind: FieldKind::of(&field.ty),
+                    };
+                    (ident.to_string(), capture_field)
+                })
+            })
+            .collect(),
+        Fields::Unnamed(fields_unnamed) => fields_unnamed
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let capture_field = CaptureField {
+                    ident: unnamed_field_index_item(index),
+                    kind: FieldKind::of(&field.ty),
+                };
+                (index.to_string(), capture_field)
+            })
+            .collect(),
+        Fields::Unit => HashMap::new(),
+    }
+}
+
+/// This assumes that the variant/struct has been destructured.
+fn write_for_tokens(
+    tokens: &[ShadowMatcherToken],
+    fields: &HashMap<String, CaptureField>,
+) -> TokenStream {
+    tokens
+        .iter()
+        .map(|token| match token {
+            ShadowMatcherToken::Exact(lit) => {
                 quote! {
-                    state = state.or_else(|| #name.build_route_section(&mut buf));
+                    write!(buf, "{}", #lit).unwrap();
                 }
             }
+            ShadowMatcherToken::Alternation(alternatives) => {
+                // The first alternative is the canonical one.
+                let lit = &alternatives[0];
+                quote! {
+                    write!(buf, "{}", #lit).unwrap();
+                }
+            }
+            ShadowMatcherToken::Capture(capture) => {
+                let writer = write_for_capture(capture, fields);
+                match capture {
+                    ShadowCaptureVariant::Named(_)
+                    | ShadowCaptureVariant::Unnamed
+                    | ShadowCaptureVariant::NamedConstrained { .. } => {
+                        // A section can't contain a '/', so it has to be encoded.
+                        quote! {
+                            let __section_start = buf.len();
+                            #writer
+                            let __section = buf.split_off(__section_start);
+                            buf.push_str(&__section.replace('/', "%2F"));
+                        }
+                    }
+                    _ => writer,
+                }
+            }
+            ShadowMatcherToken::End => quote! {},
+            ShadowMatcherToken::Query(params) => {
+                // The first parameter that is written is preceded by a '?', the rest by a '&'.
+                // Parameters captured into `Option` fields are only written if they are `Some`,
+                // and those captured into `Vec` fields are written once for every item.
+                let writers = params.iter().map(|param| {
+                    let key = &param.key;
+                    let write_key = quote! {
+                        let __separator = if buf.len() == __query_start { '?' } else { '&' };
+                        write!(buf, "{}{}=", __separator, #key).unwrap();
+                    };
+                    match &param.value {
+                        ShadowQueryValue::Exact(value) => quote! {
+                            #write_key
+                            write!(buf, "{}", #value).unwrap();
+                        },
+                        ShadowQueryValue::Capture(capture)
+                        | ShadowQueryValue::OptionalCapture(capture)
+                        | ShadowQueryValue::DefaultedCapture { capture, .. } => {
+                            match capture.name().and_then(|name| fields.get(name)) {
+                                Some(CaptureField {
+                                    ident,
+                                    kind: FieldKind::Vec,
+                                }) => quote! {
+                                    for __item in #ident {
+                                        #write_key
+                                        state = state.or_else(|| __item.build_route_section(buf));
+                                    }
+                                },
+                                Some(CaptureField {
+                                    ident,
+                                    kind: FieldKind::Option,
+                                }) => {
+                                    let write_value = write_for_capture(capture, fields);
+                                    quote! {
+                                        if #ident.is_some() {
+                                            #write_key
+                                            #write_value
+                                        }
+                                    }
+                                }
+                                _ => {
+                                    let write_value = write_for_capture(capture, fields);
+                                    quote! {
+                                        #write_key
+                                        #write_value
+                                    }
+                                }
+                            }
+                        }
+                    }
+                });
+                quote! {
+                    let __query_start = buf.len();
+                    #(#writers)*
+                }
+            }
+            ShadowMatcherToken::Optional(optional) => {
+                let writers = write_for_tokens(optional, fields);
+                match optional_presence(optional, fields) {
+                    Presence::Always => writers,
+                    Presence::Never => quote! {},
+                    Presence::When(condition) => quote! {
+                        if #condition {
+                            #writers
+                        }
+                    },
+                }
+            }
+        })
+        .collect()
+}
+
+/// This assumes that the variant/struct has been destructured.
+fn write_for_capture(
+    capture: &ShadowCaptureVariant,
+    fields: &HashMap<String, CaptureField>,
+) -> TokenStream {
+    let name = capture
+        .name()
+        .expect("Unnamed matcher sections not allowed for named field types");
+    match fields.get(name) {
+        // `prepare_vec_captures` rejects `Vec` fields captured outside of the query.
+        Some(CaptureField {
+            kind: FieldKind::Vec,
+            ..
+        }) => unreachable!("`Vec` fields can only be captured by query parameters"),
+        Some(CaptureField {
+            ident,
+            kind: FieldKind::Option,
+        }) => quote! {
+            if let ::std::option::Option::Some(#ident) = #ident {
+                state = state.or_else(|| #ident.build_route_section(buf));
+            }
+        },
+        Some(CaptureField { ident, .. }) => quote! {
+            state = state.or_else(|| #ident.build_route_section(buf));
         },
-        ShadowMatcherToken::End => quote! {},
+        None => {
+            let ident = Ident::new(name, Span::call_site());
+            quote! {
+                state = state.or_else(|| #ident.build_route_section(buf));
+            }
+        }
     }
 }
 
+/// Indicates if an optional section should be written when building a route.
+enum Presence {
+    Always,
+    Never,
+    When(TokenStream),
+}
+
+/// Optional sections are written if all of the `Option` fields captured within them are `Some`.
+///
+/// Sections that only contain literals are never written, and sections that only contain other
+/// optional sections are written if any of those are.
+fn optional_presence(
+    tokens: &[ShadowMatcherToken],
+    fields: &HashMap<String, CaptureField>,
+) -> Presence {
+    let captures = tokens
+        .iter()
+        .filter_map(|token| match token {
+            ShadowMatcherToken::Capture(capture) => Some(capture),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if !captures.is_empty() {
+        let conditions = captures
+            .into_iter()
+            .filter_map(|capture| capture.name().and_then(|name| fields.get(name)))
+            .filter(|field| field.kind == FieldKind::Option)
+            .map(|field| {
+                let ident = &field.ident;
+                quote! {#ident.is_some()}
+            })
+            .collect::<Vec<_>>();
+        return if conditions.is_empty() {
+            Presence::Always
+        } else {
+            Presence::When(quote! {#(#conditions &&)* true})
+        };
+    }
+
+    let mut conditions = vec![];
+    for token in tokens {
+        if let ShadowMatcherToken::Optional(optional) = token {
+            match optional_presence(optional, fields) {
+                Presence::Always => return Presence::Always,
+                Presence::Never => {}
+                Presence::When(condition) => conditions.push(condition),
+            }
+        }
+    }
+    if conditions.is_empty() {
+        Presence::Never
+    } else {
+        Presence::When(quote! {#((#conditions) ||)* false})
+    }
+}
 
 /// Creates an ident used for destructuring unnamed fields.
 ///
@@ -1,18 +1,185 @@
 use crate::switch::shadow::{ShadowCaptureVariant, ShadowMatcherToken};
-use syn::{spanned::Spanned, Attribute, Lit, Meta, MetaNameValue};
-use yew_router_route_parser::FieldNamingScheme;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use std::ops::Range;
+use syn::{spanned::Spanned, Attribute, Lit, LitStr, Meta, MetaNameValue, NestedMeta};
+use yew_router_route_parser::{FieldNamingScheme, PrettyParseError};
+
+/// The order that enum variants are tried in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rank {
+    /// Variants are tried from top to bottom.
+    Declaration,
+    /// Variants are tried from most to least specific.
+    Specificity,
+}
+
+/// How a `/` at the end of the path of a route is treated.
+///
+/// Mirrors `yew_router::matcher::TrailingSlash`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TrailingSlash {
+    Strict,
+    Ignore,
+    Redirect,
+}
+
+impl ToTokens for TrailingSlash {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let variant = match self {
+            TrailingSlash::Strict => quote! {Strict},
+            TrailingSlash::Ignore => quote! {Ignore},
+            TrailingSlash::Redirect => quote! {Redirect},
+        };
+        tokens.extend(quote! {
+            ::yew_router::matcher::TrailingSlash::#variant
+        })
+    }
+}
+
+/// Settings of the matcher generated for each item.
+#[derive(Clone, Copy)]
+pub struct MatcherSettings {
+    pub case_insensitive: bool,
+    pub trailing_slash: TrailingSlash,
+}
+
+impl MatcherSettings {
+    /// Overrides the settings with those given to a variant with `#[switch(...)]`.
+    pub fn for_variant(mut self, attributes: &[Attribute]) -> syn::Result<Self> {
+        for setting in switch_settings(attributes)? {
+            if !self.apply(&setting) {
+                return Err(syn::Error::new_spanned(
+                    setting,
+                    "only `case_sensitive` and `case_insensitive` can be set on a variant",
+                ));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Applies a setting that can be given to both containers and variants.
+    ///
+    /// Returns `false` if the setting isn't one of those.
+    fn apply(&mut self, setting: &NestedMeta) -> bool {
+        match setting {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("case_sensitive") => {
+                self.case_insensitive = false;
+                true
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("case_insensitive") => {
+                self.case_insensitive = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl ToTokens for MatcherSettings {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let MatcherSettings {
+            case_insensitive,
+            trailing_slash,
+        } = self;
+        tokens.extend(quote! {
+            ::yew_router::matcher::MatcherSettings {
+                case_insensitive: #case_insensitive,
+                ignore_unknown_query_params: true,
+                trailing_slash: #trailing_slash,
+            }
+        })
+    }
+}
+
+/// Gets the settings within every `#[switch(...)]` attribute.
+fn switch_settings(attributes: &[Attribute]) -> syn::Result<Vec<NestedMeta>> {
+    let mut settings = vec![];
+    for attr in attributes
+        .iter()
+        .filter(|attr| attr.path.is_ident("switch"))
+    {
+        match attr.parse_meta()? {
+            Meta::List(list) => settings.extend(list.nested),
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected a list of settings, like `#[switch(case_sensitive)]`",
+                ))
+            }
+        }
+    }
+    Ok(settings)
+}
+
+/// Settings for the whole struct or enum, specified with `#[switch(...)]`.
+pub struct ContainerAttributes {
+    pub rank: Rank,
+    pub deny_unreachable: bool,
+    pub matcher_settings: MatcherSettings,
+}
+
+impl ContainerAttributes {
+    pub fn from_attributes(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut container_attributes = ContainerAttributes {
+            rank: Rank::Declaration,
+            deny_unreachable: false,
+            matcher_settings: MatcherSettings {
+                case_insensitive: true,
+                trailing_slash: TrailingSlash::Strict,
+            },
+        };
+        for setting in switch_settings(attributes)? {
+            if container_attributes.matcher_settings.apply(&setting) {
+                continue;
+            }
+            match setting {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("deny_unreachable") => {
+                    container_attributes.deny_unreachable = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(mnv)) if mnv.path.is_ident("rank") => {
+                    container_attributes.rank = match &mnv.lit {
+                        Lit::Str(s) if s.value() == "declaration" => Rank::Declaration,
+                        Lit::Str(s) if s.value() == "specificity" => Rank::Specificity,
+                        lit => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "expected `\"declaration\"` or `\"specificity\"`",
+                            ))
+                        }
+                    }
+                }
+                NestedMeta::Meta(Meta::NameValue(mnv)) if mnv.path.is_ident("trailing_slash") => {
+                    container_attributes.matcher_settings.trailing_slash = match &mnv.lit {
+                        Lit::Str(s) if s.value() == "strict" => TrailingSlash::Strict,
+                        Lit::Str(s) if s.value() == "ignore" => TrailingSlash::Ignore,
+                        Lit::Str(s) if s.value() == "redirect" => TrailingSlash::Redirect,
+                        lit => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "expected `\"strict\"`, `\"ignore\"`, or `\"redirect\"`",
+                            ))
+                        }
+                    }
+                }
+                setting => return Err(syn::Error::new_spanned(setting, "unknown switch setting")),
+            }
+        }
+        Ok(container_attributes)
+    }
+}
 
 pub enum AttrToken {
-    To(String),
+    To(LitStr),
     End,
     Rest(Option<String>),
 }
 
 impl AttrToken {
     pub fn convert_attributes_to_tokens(attributes: Vec<Attribute>) -> syn::Result<Vec<Self>> {
-        fn get_meta_name_value_str(mnv: &MetaNameValue) -> syn::Result<String> {
+        fn get_meta_name_value_lit_str(mnv: &MetaNameValue) -> syn::Result<LitStr> {
             match &mnv.lit {
-                Lit::Str(s) => Ok(s.value()),
+                Lit::Str(s) => Ok(s.clone()),
                 lit => Err(syn::Error::new_spanned(lit, "expected a string literal")),
             }
         }
@@ -27,9 +194,10 @@ impl AttrToken {
                         mnv.path
                             .get_ident()
                             .and_then(|ident| match ident.to_string().as_str() {
-                                "to" => Some(get_meta_name_value_str(&mnv).map(AttrToken::To)),
+                                "to" => Some(get_meta_name_value_lit_str(&mnv).map(AttrToken::To)),
                                 "rest" => Some(
-                                    get_meta_name_value_str(&mnv).map(|s| AttrToken::Rest(Some(s))),
+                                    get_meta_name_value_lit_str(&mnv)
+                                        .map(|s| AttrToken::Rest(Some(s.value()))),
                                 ),
                                 _ => None,
                             })
@@ -61,17 +229,31 @@ impl AttrToken {
             .collect()
     }
 
+    /// Joins the tokens back into the route matcher string that they are equivalent to.
+    pub fn matcher_string(tokens: &[AttrToken]) -> String {
+        tokens
+            .iter()
+            .map(|token| match token {
+                AttrToken::To(lit) => lit.value(),
+                AttrToken::End => "!".to_string(),
+                AttrToken::Rest(Some(capture_name)) => format!("{{*:{}}}", capture_name),
+                AttrToken::Rest(None) => "{*}".to_string(),
+            })
+            .collect()
+    }
+
     /// The id is an unique identifier that allows otherwise unnamed captures to still be captured
     /// with unique names.
     pub fn into_shadow_matcher_tokens(
         self,
         id: usize,
         field_naming_scheme: FieldNamingScheme,
-    ) -> Vec<ShadowMatcherToken> {
-        match self {
-            AttrToken::To(matcher_string) => {
+    ) -> syn::Result<Vec<ShadowMatcherToken>> {
+        Ok(match self {
+            AttrToken::To(lit) => {
+                let matcher_string = lit.value();
                 yew_router_route_parser::parse_str_and_optimize_tokens(&matcher_string, field_naming_scheme)
-                    .expect("Invalid Matcher") // This is the point where users should see an error message if their matcher string has some syntax error.
+                    .map_err(|error| parse_error_to_syn_error(&lit, &error))? // This is the point where users should see an error message if their matcher string has some syntax error.
                     .into_iter()
                     .map(crate::switch::shadow::ShadowMatcherToken::from)
                     .collect()
@@ -83,6 +265,59 @@ impl AttrToken {
             AttrToken::Rest(None) => vec![ShadowMatcherToken::Capture(
                 ShadowCaptureVariant::ManyNamed(id.to_string()),
             )],
-        }
+        })
+    }
+}
+
+/// Converts the parse error into a compile error that points at the matcher string.
+///
+/// If the compiler supports it, the span is narrowed down to the character that the error was
+/// encountered at, otherwise the whole literal is used.
+fn parse_error_to_syn_error(lit: &LitStr, error: &PrettyParseError) -> syn::Error {
+    syn::Error::new(error_span(lit, error.offset()), format!("{:?}", error))
+}
+
+fn error_span(lit: &LitStr, offset: usize) -> Span {
+    let token = lit.token();
+    error_range(&token.to_string(), &lit.value(), offset)
+        .and_then(|range| token.subspan(range))
+        .unwrap_or_else(|| lit.span())
+}
+
+/// Gets the range of the literal's source that an error at the offset into its value points at.
+///
+/// Offsets into the value only correspond to offsets into the source of plain strings
+/// without escape sequences, so `None` is returned for any other literal.
+fn error_range(source: &str, value: &str, offset: usize) -> Option<Range<usize>> {
+    if source.len() != value.len() + 2 || !source.starts_with('"') {
+        return None;
+    }
+    // Skip the opening quote. Errors at the end of the input point at the closing quote.
+    let start = (offset + 1).min(source.len() - 1);
+    Some(start..start + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_range_points_at_offset() {
+        let source = r#""/users/{id""#;
+        assert_eq!(error_range(source, "/users/{id", 7), Some(8..9));
+        assert_eq!(error_range(source, "/users/{id", 0), Some(1..2));
+    }
+
+    #[test]
+    fn error_range_at_end_points_at_closing_quote() {
+        let source = r#""/users/{id""#;
+        assert_eq!(error_range(source, "/users/{id", 10), Some(11..12));
+        assert_eq!(error_range(source, "/users/{id", 20), Some(11..12));
+    }
+
+    #[test]
+    fn error_range_not_found_for_escapes_or_raw_strings() {
+        assert_eq!(error_range(r#""/a\tb{""#, "/a\tb{", 5), None);
+        assert_eq!(error_range(r##"r#"/users/{id"#"##, "/users/{id", 7), None);
     }
 }
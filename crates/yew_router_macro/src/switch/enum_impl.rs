@@ -1,3 +1,4 @@
+use crate::switch::{attribute::TrailingSlash, explain::Explain, routes::Routes};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
@@ -11,6 +12,9 @@ mod from_route_part;
 pub struct EnumInner<'a> {
     pub from_route_part: FromRoutePart<'a>,
     pub build_route_section: BuildRouteSection<'a>,
+    pub explain: Explain<'a>,
+    pub routes: Routes<'a>,
+    pub trailing_slash: TrailingSlash,
 }
 
 impl<'a> ToTokens for EnumInner<'a> {
@@ -18,10 +22,19 @@ impl<'a> ToTokens for EnumInner<'a> {
         let EnumInner {
             from_route_part,
             build_route_section,
+            explain,
+            routes,
+            trailing_slash,
         } = self;
         tokens.extend(quote! {
             #from_route_part
             #build_route_section
+            #explain
+            #routes
+
+            fn trailing_slash() -> ::yew_router::matcher::TrailingSlash {
+                #trailing_slash
+            }
         });
     }
 }
@@ -1,35 +1,56 @@
-use crate::switch::SwitchItem;
+use crate::switch::{
+    attribute::TrailingSlash, build_field_from_capture, query_captures, shadow::ShadowMatcherToken,
+    SwitchItem,
+};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{Field, Fields, Type};
+use yew_router_route_parser::{KeyPart, PrefixTrie, TrieEdge, TrieNode};
 
 pub struct FromRoutePart<'a> {
     pub switch_variants: &'a [SwitchItem],
     pub enum_ident: &'a Ident,
 }
 
-
 impl<'a> ToTokens for FromRoutePart<'a> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let variant_matchers = self.switch_variants.iter().map(|sv| {
+        let variant_matchers = self.switch_variants.iter().enumerate().map(|(index, sv)| {
             let SwitchItem {
                 matcher,
                 ident,
                 fields,
+                settings,
+                ..
             } = sv;
-            let build_from_captures = build_variant_from_captures(&self.enum_ident, ident, fields);
-            let matcher = super::super::build_matcher_from_tokens(&matcher);
+            let build_from_captures =
+                build_variant_from_captures(&self.enum_ident, ident, fields, matcher);
+            let matcher = super::super::build_matcher_from_tokens(&matcher, settings);
 
             quote! {
-                #matcher
-                #build_from_captures
+                #index => {
+                    #matcher
+                    #build_from_captures
+                }
             }
         });
+        let trie = build_trie(self.switch_variants);
+        let variant_count = self.switch_variants.len();
 
         tokens.extend(quote!{
-            fn from_route_part<__T>(route: String, mut state: Option<__T>) -> (::std::option::Option<Self>, ::std::option::Option<__T>) {
-                let route_string = route;
-                #(#variant_matchers)*
+            fn from_route_part<__T>(route: String, state: Option<__T>) -> (::std::option::Option<Self>, ::std::option::Option<__T>) {
+                Self::from_route_part_ref(&route, state)
+            }
+
+            fn from_route_part_ref<__T>(route: &str, mut state: Option<__T>) -> (::std::option::Option<Self>, ::std::option::Option<__T>) {
+                static TRIE: ::yew_router::matcher::PrefixTrie = #trie;
+                // Only the variants whose literals the route contains are tried.
+                let mut candidates = [0usize; #variant_count];
+                for candidate in TRIE.candidates(route, &mut candidates) {
+                    match *candidate {
+                        #(#variant_matchers)*
+                        _ => unreachable!("the prefix tree only holds the indices of variants"),
+                    }
+                }
 
                 (::std::option::Option::None, state)
             }
@@ -37,12 +58,86 @@ impl<'a> ToTokens for FromRoutePart<'a> {
     }
 }
 
+/// Builds a prefix tree over the literals and captures of the variants' matchers.
+fn build_trie(switch_variants: &[SwitchItem]) -> TokenStream {
+    let PrefixTrie { nodes } = PrefixTrie::new(switch_variants.iter().map(trie_key));
+    let nodes = nodes.iter().map(|node| {
+        let TrieNode {
+            edges,
+            capture,
+            matchers,
+        } = node;
+        let edges = edges.iter().map(|TrieEdge { label, node }| {
+            let label: &str = label;
+            quote! {
+                ::yew_router::matcher::TrieEdge {
+                    label: ::std::borrow::Cow::Borrowed(#label),
+                    node: #node,
+                }
+            }
+        });
+        let capture = match capture {
+            Some(capture) => quote! { ::std::option::Option::Some(#capture) },
+            None => quote! { ::std::option::Option::None },
+        };
+        let matchers = matchers.iter();
+        quote! {
+            ::yew_router::matcher::TrieNode {
+                edges: ::std::borrow::Cow::Borrowed(&[#(#edges),*]),
+                capture: #capture,
+                matchers: ::std::borrow::Cow::Borrowed(&[#(#matchers),*]),
+            }
+        }
+    });
+    quote! {
+        ::yew_router::matcher::PrefixTrie {
+            nodes: ::std::borrow::Cow::Borrowed(&[#(#nodes),*]),
+        }
+    }
+}
+
+/// Gets the literals and captures that the variant's matcher starts with.
+///
+/// The key ends at the first token that can't be described by them, such as an optional section.
+/// If trailing slashes aren't strict, the route might not have the `/` that ends the path of the
+/// matcher, so it isn't part of the key.
+fn trie_key(sv: &SwitchItem) -> Vec<KeyPart<'_>> {
+    let mut key = vec![];
+    let mut tokens = sv.matcher.iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            ShadowMatcherToken::Exact(literal) => {
+                let ends_path = match tokens.peek() {
+                    None | Some(ShadowMatcherToken::End) | Some(ShadowMatcherToken::Query(_)) => {
+                        true
+                    }
+                    Some(_) => false,
+                };
+                let literal = match literal.strip_suffix('/') {
+                    Some(without_slash)
+                        if ends_path && sv.settings.trailing_slash != TrailingSlash::Strict =>
+                    {
+                        without_slash
+                    }
+                    _ => literal.as_str(),
+                };
+                key.push(KeyPart::Literal(literal));
+            }
+            ShadowMatcherToken::Capture(_) => key.push(KeyPart::Capture),
+            _ => break,
+        }
+    }
+    key
+}
+
 /// Once the 'captures' exists, attempt to populate the fields from the list of captures.
 fn build_variant_from_captures(
     enum_ident: &Ident,
     variant_ident: &Ident,
     fields: &Fields,
+    matcher: &[ShadowMatcherToken],
 ) -> TokenStream {
+    let query_captures = query_captures(matcher);
     match fields {
         Fields::Named(named_fields) => {
             let (field_declarations, fields): (Vec<_>, Vec<_>) = named_fields
@@ -56,30 +151,10 @@ fn build_variant_from_captures(
                     })
                 })
                 .map(|(field_name, key, field_ty): (&Ident, String, &Type)| {
+                    let field_from_capture =
+                        build_field_from_capture(&key, field_ty, query_captures.contains(&key));
                     let field_decl = quote! {
-                        let #field_name = {
-                            let (v, s) = match captures.remove(#key) {
-                                ::std::option::Option::Some(value) => {
-                                    <#field_ty as ::yew_router::Switch>::from_route_part(
-                                        value,
-                                        state,
-                                    )
-                                }
-                                ::std::option::Option::None => {
-                                    (
-                                        <#field_ty as ::yew_router::Switch>::key_not_available(),
-                                        state,
-                                    )
-                                }
-                            };
-                            match v {
-                                ::std::option::Option::Some(val) => {
-                                    state = s; // Set state for the next var.
-                                    val
-                                },
-                                ::std::option::Option::None => return (None, s) // Failed
-                            }
-                        };
+                        let #field_name = #field_from_capture;
                     };
 
                     (field_decl, field_name)
@@ -87,8 +162,8 @@ fn build_variant_from_captures(
                 .unzip();
 
             quote! {
-                let mut state = if let ::std::option::Option::Some(mut captures) = matcher
-                    .capture_route_into_map(&route_string)
+                state = if let ::std::option::Option::Some(mut captures) = matcher
+                    .capture_route_into_ref_map(route)
                     .ok()
                     .map(|x| x.1)
                 {
@@ -122,30 +197,13 @@ fn build_variant_from_captures(
                 .map(|(idx, f)| {
                     let field_ty = &f.ty;
                     let field_var_name = Ident::new(&format!("field_{}", idx), Span::call_site());
+                    let field_from_capture = build_field_from_capture(
+                        &idx.to_string(),
+                        field_ty,
+                        query_captures.contains(&idx.to_string()),
+                    );
                     let field_decl = quote! {
-                        let #field_var_name = {
-                            let (v, s) = match drain.next() {
-                                ::std::option::Option::Some(value) => {
-                                    <#field_ty as ::yew_router::Switch>::from_route_part(
-                                        value,
-                                        state,
-                                    )
-                                },
-                                ::std::option::Option::None => {
-                                    (
-                                        <#field_ty as ::yew_router::Switch>::key_not_available(),
-                                        state,
-                                    )
-                                }
-                            };
-                            match v {
-                                ::std::option::Option::Some(val) => {
-                                    state = s; // Set state for the next var.
-                                    val
-                                },
-                                ::std::option::Option::None => return (None, s) // Failed
-                            }
-                        };
+                        let #field_var_name = #field_from_capture;
                     };
 
                     (field_decl, field_var_name)
@@ -153,12 +211,11 @@ fn build_variant_from_captures(
                 .unzip();
 
             quote! {
-                let mut state = if let ::std::option::Option::Some(mut captures) = matcher
-                    .capture_route_into_vec(&route_string)
+                state = if let ::std::option::Option::Some(mut captures) = matcher
+                    .capture_route_into_ref_map(route)
                     .ok()
                     .map(|x| x.1)
                 {
-                    let mut drain = captures.drain(..);
                     let create_item = || {
                         #(#field_declarations)*
 
@@ -183,7 +240,7 @@ fn build_variant_from_captures(
         }
         Fields::Unit => {
             quote! {
-                let mut state = if let ::std::option::Option::Some(_captures) = matcher.capture_route_into_map(&route_string).ok().map(|x| x.1) {
+                state = if let ::std::option::Option::Some(_captures) = matcher.capture_route_into_ref_map(route).ok().map(|x| x.1) {
                     return (::std::option::Option::Some(#enum_ident::#variant_ident), state);
                 } else {
                     state
@@ -0,0 +1,172 @@
+//! Generates `Switch::explain`, which reports why each variant did or didn't match a route.
+use crate::switch::{
+    build_matcher_from_tokens, decode_query_value, query_captures, wrapped_type, FieldKind,
+    SwitchItem,
+};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Fields, Type};
+
+pub struct Explain<'a> {
+    /// The items in the order that they are tried in.
+    pub switch_items: &'a [SwitchItem],
+}
+
+impl<'a> ToTokens for Explain<'a> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let variant_explanations = self.switch_items.iter().map(explain_item);
+        tokens.extend(quote! {
+            fn explain(route: &str) -> ::yew_router::switch::Explanation {
+                let mut variants = ::std::vec::Vec::new();
+                #(#variant_explanations)*
+                ::yew_router::switch::Explanation {
+                    route: route.to_string(),
+                    variants,
+                }
+            }
+        });
+    }
+}
+
+/// Explains a single item, returning early if it matched.
+///
+/// Every variant is diagnosed, instead of only those that the prefix tree would select, so that
+/// the explanation covers variants that were skipped as well.
+fn explain_item(item: &SwitchItem) -> TokenStream {
+    let SwitchItem {
+        matcher,
+        ident,
+        fields,
+        settings,
+        ..
+    } = item;
+    let name = ident.to_string();
+    let query_captures = query_captures(matcher);
+    let matcher = build_matcher_from_tokens(matcher, settings);
+    let field_checks = match fields {
+        Fields::Named(fields_named) => fields_named
+            .named
+            .iter()
+            .filter_map(|field| {
+                field
+                    .ident
+                    .as_ref()
+                    .map(|ident| ident.to_string())
+                    .map(|key| check_field(&key, &field.ty, query_captures.contains(&key)))
+            })
+            .collect::<Vec<_>>(),
+        Fields::Unnamed(fields_unnamed) => fields_unnamed
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let key = index.to_string();
+                check_field(&key, &field.ty, query_captures.contains(&key))
+            })
+            .collect(),
+        Fields::Unit => vec![],
+    };
+    let captures = if field_checks.is_empty() {
+        quote! { _ }
+    } else {
+        quote! { captures }
+    };
+
+    quote! {
+        {
+            #matcher
+            let outcome = match matcher.diagnose(route) {
+                ::std::result::Result::Err(failure) => {
+                    ::yew_router::switch::Outcome::NoMatch(failure)
+                }
+                ::std::result::Result::Ok(#captures) => {
+                    let check_fields = || {
+                        #(#field_checks)*
+                        ::yew_router::switch::Outcome::Matched
+                    };
+                    check_fields()
+                }
+            };
+            let matched = outcome == ::yew_router::switch::Outcome::Matched;
+            variants.push(::yew_router::switch::VariantExplanation {
+                name: #name,
+                outcome,
+            });
+            if matched {
+                return ::yew_router::switch::Explanation {
+                    route: route.to_string(),
+                    variants,
+                };
+            }
+        }
+    }
+}
+
+/// Creates a statement that returns a `FieldFailed` outcome if the field can't be created from
+/// the captures stored under the key, mirroring `build_field_from_capture`.
+///
+/// This assumes that `captures` (a `RefCaptures`) is in scope.
+fn check_field(key: &str, field_ty: &Type, query: bool) -> TokenStream {
+    let decode = decode_query_value(query);
+    let converts = |ty: &Type| {
+        quote! {
+            {
+                #decode
+                <#ty as ::yew_router::Switch>::from_route_part_ref::<()>(
+                    value,
+                    ::std::option::Option::None,
+                )
+                .0
+                .is_some()
+            }
+        }
+    };
+    let fail_with_value = quote! {
+        return ::yew_router::switch::Outcome::FieldFailed {
+            field: #key,
+            value: ::std::option::Option::Some(value.to_string()),
+        };
+    };
+    match FieldKind::of(field_ty) {
+        FieldKind::Option => {
+            let converts = converts(wrapped_type(field_ty, "Option").expect("Option field"));
+            quote! {
+                if let ::std::option::Option::Some(value) = captures.get(#key).and_then(|values| values.first()) {
+                    if !#converts {
+                        #fail_with_value
+                    }
+                }
+            }
+        }
+        FieldKind::Vec => {
+            let converts = converts(wrapped_type(field_ty, "Vec").expect("Vec field"));
+            quote! {
+                for value in captures.get(#key).into_iter().flatten() {
+                    if !#converts {
+                        #fail_with_value
+                    }
+                }
+            }
+        }
+        FieldKind::Single => {
+            let converts = converts(field_ty);
+            quote! {
+                match captures.get(#key).and_then(|values| values.first()) {
+                    ::std::option::Option::Some(value) => {
+                        if !#converts {
+                            #fail_with_value
+                        }
+                    }
+                    ::std::option::Option::None => {
+                        if <#field_ty as ::yew_router::Switch>::key_not_available().is_none() {
+                            return ::yew_router::switch::Outcome::FieldFailed {
+                                field: #key,
+                                value: ::std::option::Option::None,
+                            };
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Detects variants that can never be matched, because a variant declared above them will match
+//! every route that they would.
+//!
+//! The analysis is conservative - a variant is only reported if it is certain that it is shadowed.
+//! Matchers containing optional sections, alternations, queries, or constrained or numbered
+//! captures in the shadowing variant are never reported.
+//! A variant with fields only shadows a later variant if every field is a `String` that is created
+//! from literal characters of the later matcher, because parsing any other type, or decoding a
+//! percent-encoded escape, might fail.
+//! Whatever follows the later matcher's pieces in a route is assumed to be valid to decode.
+//!
+//! The check only runs for enums with `#[switch(deny_unreachable)]`.
+use crate::switch::{
+    attribute::TrailingSlash,
+    shadow::{ShadowCaptureVariant, ShadowMatcherToken},
+    wrapped_type, FieldKind, SwitchItem,
+};
+use syn::{Fields, Type};
+
+/// Produces an error for every variant that is shadowed by a variant declared above it.
+pub fn check_reachability(items: &[SwitchItem]) -> syn::Result<()> {
+    let mut errors = items.iter().enumerate().filter_map(|(index, later)| {
+        items[..index]
+            .iter()
+            .find(|earlier| shadows(earlier, later))
+            .map(|earlier| {
+                syn::Error::new(
+                    later.ident.span(),
+                    format!(
+                        "`{}` can never be matched, because `{}` is declared above it and matches every route that it does",
+                        later.ident, earlier.ident
+                    ),
+                )
+            })
+    });
+    match errors.next() {
+        Some(mut error) => {
+            errors.for_each(|other| error.combine(other));
+            Err(error)
+        }
+        None => Ok(()),
+    }
+}
+
+/// A single character of a literal, or a capture section, of the later variant's matcher.
+#[derive(PartialEq)]
+enum Piece {
+    Char(char),
+    Capture { single: bool },
+    End,
+}
+
+/// The pieces of the later matcher that are consumed by a capture of the earlier matcher.
+struct Captured<'a> {
+    name: Option<&'a str>,
+    pieces: &'a [Piece],
+}
+
+fn shadows(earlier: &SwitchItem, later: &SwitchItem) -> bool {
+    match pieces(&later.matcher) {
+        Some(later_pieces) => {
+            !slash_optional(later, &later_pieces)
+                && (earlier.settings.case_insensitive || !later.settings.case_insensitive)
+                && matches_all(&earlier.matcher, &later_pieces)
+                    .map_or(false, |captured| always_populated(earlier, &captured))
+        }
+        None => false,
+    }
+}
+
+/// Checks if the later matcher ends its path with a `/` that routes might omit, in which case it
+/// matches routes that aren't represented by its pieces.
+fn slash_optional(later: &SwitchItem, pieces: &[Piece]) -> bool {
+    let path = match pieces {
+        [path @ .., Piece::End] => path,
+        path => path,
+    };
+    later.settings.trailing_slash != TrailingSlash::Strict && path.last() == Some(&Piece::Char('/'))
+}
+
+/// Flattens the matcher into pieces, if it only consists of tokens that can be analyzed.
+fn pieces(tokens: &[ShadowMatcherToken]) -> Option<Vec<Piece>> {
+    let mut pieces = vec![];
+    for token in tokens {
+        match token {
+            ShadowMatcherToken::Exact(literal) => pieces.extend(literal.chars().map(Piece::Char)),
+            ShadowMatcherToken::Capture(capture) => {
+                let single = match capture {
+                    ShadowCaptureVariant::Named(_)
+                    | ShadowCaptureVariant::Unnamed
+                    | ShadowCaptureVariant::NamedConstrained { .. } => true,
+                    ShadowCaptureVariant::ManyNamed(_) | ShadowCaptureVariant::ManyUnnamed => false,
+                    ShadowCaptureVariant::NumberedNamed { .. }
+                    | ShadowCaptureVariant::NumberedUnnamed { .. } => return None,
+                };
+                pieces.push(Piece::Capture { single })
+            }
+            ShadowMatcherToken::End => pieces.push(Piece::End),
+            ShadowMatcherToken::Alternation(_)
+            | ShadowMatcherToken::Query(_)
+            | ShadowMatcherToken::Optional(_) => return None,
+        }
+    }
+    Some(pieces)
+}
+
+/// Checks if the earlier matcher matches every route that the later matcher's pieces would,
+/// returning the pieces that each of its captures consumes if it does.
+///
+/// This mirrors how the matcher consumes routes: literals must be present, captures consume
+/// everything up to the first occurrence of the literal that follows them, and the matcher
+/// succeeds as soon as its tokens are exhausted, regardless of what remains of the route.
+fn matches_all<'a>(
+    earlier: &'a [ShadowMatcherToken],
+    later: &'a [Piece],
+) -> Option<Vec<Captured<'a>>> {
+    let mut captured = vec![];
+    let mut position = 0;
+    let mut tokens = earlier.iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            ShadowMatcherToken::Exact(literal) => {
+                for c in literal.chars() {
+                    if later.get(position) != Some(&Piece::Char(c)) {
+                        return None;
+                    }
+                    position += 1;
+                }
+            }
+            ShadowMatcherToken::Capture(capture) => {
+                let single = match capture {
+                    ShadowCaptureVariant::Named(_) | ShadowCaptureVariant::Unnamed => true,
+                    ShadowCaptureVariant::ManyNamed(_) | ShadowCaptureVariant::ManyUnnamed => false,
+                    _ => return None,
+                };
+                let start = position;
+                match tokens.peek() {
+                    None => {
+                        let rest = &later[position..];
+                        if !capture_matches_rest(single, rest) {
+                            return None;
+                        }
+                        let pieces = match rest {
+                            [pieces @ .., Piece::End] => pieces,
+                            pieces => pieces,
+                        };
+                        captured.push(Captured {
+                            name: capture.name(),
+                            pieces,
+                        });
+                        return Some(captured);
+                    }
+                    Some(ShadowMatcherToken::Exact(delimiter)) => {
+                        // The delimiter has to be found within the literal, otherwise it might
+                        // be found within something captured by the later matcher.
+                        let mut literal = String::new();
+                        for piece in &later[position..] {
+                            match piece {
+                                Piece::Char(c) => literal.push(*c),
+                                _ => break,
+                            }
+                        }
+                        match literal.find(delimiter.as_str()) {
+                            Some(index) => position += literal[..index].chars().count(),
+                            None => return None,
+                        }
+                    }
+                    Some(_) => return None,
+                }
+                captured.push(Captured {
+                    name: capture.name(),
+                    pieces: &later[start..position],
+                });
+            }
+            ShadowMatcherToken::End => {
+                return Some(captured).filter(|_| later.get(position) == Some(&Piece::End))
+            }
+            ShadowMatcherToken::Alternation(_)
+            | ShadowMatcherToken::Query(_)
+            | ShadowMatcherToken::Optional(_) => return None,
+        }
+    }
+    Some(captured)
+}
+
+/// Checks if a capture at the end of the earlier matcher will match whatever remains.
+fn capture_matches_rest(single: bool, rest: &[Piece]) -> bool {
+    // Characters that end a capture at the end of a matcher.
+    let invalid = if single { " */#&?{}=" } else { " #&?=" };
+    match rest {
+        [Piece::Char(c), ..] => !invalid.contains(*c),
+        // Captures of many sections are allowed to be empty, so they can't shadow single captures.
+        [Piece::Capture {
+            single: later_single,
+        }]
+        | [Piece::Capture {
+            single: later_single,
+        }, Piece::End] => *later_single || !single,
+        [Piece::End] => !single,
+        _ => false,
+    }
+}
+
+/// Checks if creating the item's fields from what its captures consume can never fail.
+///
+/// Every capture is percent-decoded before it is parsed, which fails for escapes that don't
+/// decode to UTF-8, so captures are only certain to be decoded if they consume literal characters
+/// other than `%`.
+/// Parsing a `String` never fails, but parsing any other type might.
+fn always_populated(item: &SwitchItem, captured: &[Captured]) -> bool {
+    match &item.fields {
+        Fields::Named(fields) => fields.named.iter().all(|field| {
+            field.ident.as_ref().map_or(false, |ident| {
+                field_populated(&ident.to_string(), &field.ty, captured)
+            })
+        }),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .all(|(index, field)| field_populated(&index.to_string(), &field.ty, captured)),
+        Fields::Unit => true,
+    }
+}
+
+fn field_populated(key: &str, ty: &Type, captured: &[Captured]) -> bool {
+    let pieces = captured
+        .iter()
+        .find(|captured| captured.name == Some(key))
+        .map(|captured| captured.pieces);
+    let decodes = |pieces: &[Piece]| {
+        pieces
+            .iter()
+            .all(|piece| matches!(piece, Piece::Char(c) if *c != '%'))
+    };
+    match (FieldKind::of(ty), pieces) {
+        (FieldKind::Single, Some(pieces)) => is_string(ty) && decodes(pieces),
+        (FieldKind::Option, Some(pieces)) => {
+            wrapped_type(ty, "Option").map_or(false, is_string) && decodes(pieces)
+        }
+        (FieldKind::Option, None) => true,
+        _ => false,
+    }
+}
+
+/// Checks if the type is a `String`, which is created from any decoded capture.
+fn is_string(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            type_path.path.segments.last().map_or(false, |segment| {
+                segment.ident == "String" && segment.arguments.is_empty()
+            })
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::switch::attribute::MatcherSettings;
+    use proc_macro2::Span;
+    use syn::{parse_quote, Fields, Ident};
+    use yew_router_route_parser::FieldNamingScheme;
+
+    fn item(matcher: &str, fields: Fields) -> SwitchItem {
+        let field_naming_scheme = match fields {
+            Fields::Named(_) => FieldNamingScheme::Named,
+            Fields::Unnamed(_) => FieldNamingScheme::Unnamed,
+            Fields::Unit => FieldNamingScheme::Unit,
+        };
+        let mut matcher_tokens: Vec<_> =
+            yew_router_route_parser::parse_str_and_optimize_tokens(matcher, field_naming_scheme)
+                .expect("should parse")
+                .into_iter()
+                .map(ShadowMatcherToken::from)
+                .collect();
+        if let FieldNamingScheme::Unnamed = field_naming_scheme {
+            crate::switch::number_captures(&mut matcher_tokens, &mut 0);
+        }
+        SwitchItem {
+            matcher: matcher_tokens,
+            matcher_string: matcher.to_string(),
+            ident: Ident::new("Item", Span::call_site()),
+            fields,
+            settings: MatcherSettings {
+                case_insensitive: true,
+                trailing_slash: TrailingSlash::Strict,
+            },
+        }
+    }
+
+    fn unit(matcher: &str) -> SwitchItem {
+        item(matcher, Fields::Unit)
+    }
+
+    #[test]
+    fn prefix_shadows_longer_route() {
+        assert!(shadows(&unit("/user"), &unit("/user/settings")));
+        assert!(!shadows(&unit("/user/settings"), &unit("/user")));
+    }
+
+    #[test]
+    fn end_only_shadows_identical_route() {
+        assert!(!shadows(&unit("/user!"), &unit("/user/settings")));
+        assert!(shadows(&unit("/user!"), &unit("/user!")));
+    }
+
+    #[test]
+    fn string_captures_of_literals_shadow() {
+        let earlier = item(
+            "/user/{name}",
+            Fields::Named(parse_quote!({ name: String })),
+        );
+        assert!(shadows(&earlier, &unit("/user/settings")));
+        let earlier = item("{*:rest}", Fields::Named(parse_quote!({ rest: String })));
+        assert!(shadows(&earlier, &unit("/about")));
+        let earlier = item("/user/{}", Fields::Unnamed(parse_quote!((String))));
+        assert!(shadows(&earlier, &unit("/user/settings")));
+    }
+
+    #[test]
+    fn captures_that_might_fail_do_not_shadow() {
+        let earlier = item("/user/{name}", Fields::Named(parse_quote!({ name: u32 })));
+        assert!(!shadows(&earlier, &unit("/user/1")));
+        let earlier = item(
+            "/user/{name}",
+            Fields::Named(parse_quote!({ name: String })),
+        );
+        // The escape might not decode.
+        assert!(!shadows(&earlier, &unit("/user/%20")));
+        let later = item("/user/{id}", Fields::Named(parse_quote!({ id: String })));
+        assert!(!shadows(&earlier, &later));
+    }
+
+    #[test]
+    fn case_sensitive_does_not_shadow_case_insensitive() {
+        let mut earlier = unit("/user");
+        earlier.settings.case_insensitive = false;
+        assert!(!shadows(&earlier, &unit("/user/settings")));
+        let mut later = unit("/user/settings");
+        later.settings.case_insensitive = false;
+        assert!(shadows(&unit("/user"), &later));
+    }
+
+    #[test]
+    fn optional_trailing_slash_is_not_shadowed() {
+        let mut later = unit("/user/");
+        later.settings.trailing_slash = TrailingSlash::Ignore;
+        assert!(!shadows(&unit("/user/"), &later));
+        assert!(shadows(&unit("/user/"), &unit("/user/")));
+    }
+
+    #[test]
+    fn unanalyzable_matchers_are_not_shadowed() {
+        assert!(!shadows(&unit("/user"), &unit("/user[/settings]")));
+        assert!(!shadows(&unit("/user"), &unit("/user/(settings|profile)")));
+    }
+
+    #[test]
+    fn fields_without_captures_are_only_populated_if_optional() {
+        let later = unit("/user/settings");
+        let named = |fields| item("/user/{name}", Fields::Named(fields));
+        assert!(shadows(
+            &named(parse_quote!({ name: Option<String> })),
+            &later
+        ));
+        assert!(shadows(
+            &named(parse_quote!({ name: String, other: Option<String> })),
+            &later
+        ));
+        assert!(!shadows(
+            &named(parse_quote!({ name: String, other: String })),
+            &later
+        ));
+        assert!(!shadows(
+            &named(parse_quote!({ name: Option<u32> })),
+            &later
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+//! Generates `Switch::routes`, which describes the route matcher of each variant.
+use crate::switch::{
+    shadow::{ShadowMatcherToken, ShadowQueryValue},
+    SwitchItem,
+};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use std::collections::HashMap;
+use syn::{Fields, Type};
+
+pub struct Routes<'a> {
+    /// The items in the order that they are tried in.
+    pub switch_items: &'a [SwitchItem],
+}
+
+impl<'a> ToTokens for Routes<'a> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let route_infos = self.switch_items.iter().map(route_info);
+        tokens.extend(quote! {
+            fn routes() -> &'static [::yew_router::switch::RouteInfo] {
+                &[#(#route_infos),*]
+            }
+        });
+    }
+}
+
+/// Describes a single item, listing every url that it matches if it has no captures.
+fn route_info(item: &SwitchItem) -> TokenStream {
+    let name = item.ident.to_string();
+    let matcher_string = &item.matcher_string;
+    let mut literals = vec![];
+    let mut captures = vec![];
+    let mut query_keys = vec![];
+    collect_parts(&item.matcher, &mut literals, &mut captures, &mut query_keys);
+    let urls = if captures.is_empty() {
+        let mut urls = vec![];
+        for url in expand_urls(&item.matcher, vec![String::new()]) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    } else {
+        vec![]
+    };
+    let field_types = field_types(&item.fields);
+    let captures = captures.iter().map(|capture| {
+        let ty = match field_types.get(capture) {
+            Some(ty) => quote! { ::std::option::Option::Some(#ty) },
+            None => quote! { ::std::option::Option::None },
+        };
+        quote! {
+            ::yew_router::switch::CaptureInfo {
+                name: #capture,
+                ty: #ty,
+            }
+        }
+    });
+
+    quote! {
+        ::yew_router::switch::RouteInfo {
+            name: #name,
+            matcher: #matcher_string,
+            literals: &[#(#literals),*],
+            captures: &[#(#captures),*],
+            query_keys: &[#(#query_keys),*],
+            urls: &[#(#urls),*],
+        }
+    }
+}
+
+/// Collects the literal sections, capture names, and query keys of the tokens, in order.
+///
+/// Captures without a name are listed as an empty string.
+fn collect_parts(
+    tokens: &[ShadowMatcherToken],
+    literals: &mut Vec<String>,
+    captures: &mut Vec<String>,
+    query_keys: &mut Vec<String>,
+) {
+    for token in tokens {
+        match token {
+            ShadowMatcherToken::Exact(lit) => literals.push(lit.clone()),
+            ShadowMatcherToken::Alternation(alternatives) => {
+                literals.extend(alternatives.iter().cloned())
+            }
+            ShadowMatcherToken::Capture(capture) => {
+                captures.push(capture.name().unwrap_or_default().to_string())
+            }
+            ShadowMatcherToken::End => {}
+            ShadowMatcherToken::Query(params) => {
+                for param in params {
+                    query_keys.push(param.key.clone());
+                    match &param.value {
+                        ShadowQueryValue::Exact(_) => {}
+                        ShadowQueryValue::Capture(capture)
+                        | ShadowQueryValue::OptionalCapture(capture)
+                        | ShadowQueryValue::DefaultedCapture { capture, .. } => {
+                            captures.push(capture.name().unwrap_or_default().to_string())
+                        }
+                    }
+                }
+            }
+            ShadowMatcherToken::Optional(optional) => {
+                collect_parts(optional, literals, captures, query_keys)
+            }
+        }
+    }
+}
+
+/// Extends each of the urls with every string that the tokens match.
+///
+/// Alternations branch into one url per alternative,
+/// and optional sections into a url without the section and urls with it.
+/// This assumes that the tokens don't contain any captures.
+fn expand_urls(tokens: &[ShadowMatcherToken], mut urls: Vec<String>) -> Vec<String> {
+    for token in tokens {
+        urls = match token {
+            ShadowMatcherToken::Exact(lit) => urls.into_iter().map(|url| url + lit).collect(),
+            ShadowMatcherToken::Alternation(alternatives) => urls
+                .iter()
+                .flat_map(|url| {
+                    alternatives
+                        .iter()
+                        .map(move |alt| format!("{}{}", url, alt))
+                })
+                .collect(),
+            ShadowMatcherToken::Capture(_) | ShadowMatcherToken::End => urls,
+            ShadowMatcherToken::Query(params) => urls
+                .into_iter()
+                .map(|mut url| {
+                    for (index, param) in params.iter().enumerate() {
+                        if let ShadowQueryValue::Exact(value) = &param.value {
+                            let separator = if index == 0 { '?' } else { '&' };
+                            url.push_str(&format!("{}{}={}", separator, param.key, value));
+                        }
+                    }
+                    url
+                })
+                .collect(),
+            ShadowMatcherToken::Optional(optional) => {
+                let with_optional = expand_urls(optional, urls.clone());
+                urls.into_iter().chain(with_optional).collect()
+            }
+        };
+    }
+    urls
+}
+
+/// Maps the name that each field is captured under to its type, as it is written in the source.
+fn field_types(fields: &Fields) -> HashMap<String, String> {
+    match fields {
+        Fields::Named(fields_named) => fields_named
+            .named
+            .iter()
+            .filter_map(|field| {
+                field
+                    .ident
+                    .as_ref()
+                    .map(|ident| (ident.to_string(), type_string(&field.ty)))
+            })
+            .collect(),
+        Fields::Unnamed(fields_unnamed) => fields_unnamed
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(index, field)| (index.to_string(), type_string(&field.ty)))
+            .collect(),
+        Fields::Unit => HashMap::new(),
+    }
+}
+
+/// Prints the type without the spaces that the token stream puts between every token,
+/// like `Option<u32>` instead of `Option < u32 >`.
+fn type_string(ty: &Type) -> String {
+    let spaced = ty.to_token_stream().to_string();
+    let chars = spaced.chars().collect::<Vec<_>>();
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_' || *c == '\'';
+    let mut string = String::new();
+    for (index, c) in chars.iter().enumerate() {
+        match c {
+            ' ' => {
+                // Spaces are only needed between words, like in `dyn Trait`.
+                let before = index.checked_sub(1).and_then(|before| chars.get(before));
+                let between_words = match (before, chars.get(index + 1)) {
+                    (Some(before), Some(after)) => is_word(before) && is_word(after),
+                    _ => false,
+                };
+                if between_words {
+                    string.push(' ');
+                }
+            }
+            ',' => string.push_str(", "),
+            c => string.push(*c),
+        }
+    }
+    string
+}
@@ -1,13 +1,20 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
-use yew_router_route_parser::{CaptureVariant, MatcherToken};
+use yew_router_route_parser::{
+    CaptureConstraint, CaptureType, CaptureVariant, CharClass, MatcherToken, QueryParam, QueryValue,
+};
 
 impl ToTokens for ShadowMatcherToken {
     fn to_tokens(&self, ts: &mut TokenStream) {
         use ShadowMatcherToken as SOT;
         let t: TokenStream = match self {
             SOT::Exact(s) => quote! {
-                ::yew_router::matcher::MatcherToken::Exact(#s.to_string())
+                ::yew_router::matcher::MatcherToken::Exact(::std::borrow::Cow::Borrowed(#s))
+            },
+            SOT::Alternation(alternatives) => quote! {
+                ::yew_router::matcher::MatcherToken::Alternation(
+                    ::std::borrow::Cow::Borrowed(&[#(::std::borrow::Cow::Borrowed(#alternatives)),*])
+                )
             },
             SOT::Capture(variant) => quote! {
                 ::yew_router::matcher::MatcherToken::Capture(#variant)
@@ -15,6 +22,12 @@ impl ToTokens for ShadowMatcherToken {
             SOT::End => quote! {
                 ::yew_router::matcher::MatcherToken::End
             },
+            SOT::Query(params) => quote! {
+                ::yew_router::matcher::MatcherToken::Query(::std::borrow::Cow::Borrowed(&[#(#params),*]))
+            },
+            SOT::Optional(tokens) => quote! {
+                ::yew_router::matcher::MatcherToken::Optional(::std::borrow::Cow::Borrowed(&[#(#tokens),*]))
+            },
         };
         ts.extend(t)
     }
@@ -24,8 +37,69 @@ impl ToTokens for ShadowMatcherToken {
 /// It should match it exactly so that this macro can expand to the original.
 pub enum ShadowMatcherToken {
     Exact(String),
+    Alternation(Vec<String>),
     Capture(ShadowCaptureVariant),
     End,
+    Query(Vec<ShadowQueryParam>),
+    Optional(Vec<ShadowMatcherToken>),
+}
+
+/// A shadow of the QueryParam type.
+pub struct ShadowQueryParam {
+    pub key: String,
+    pub value: ShadowQueryValue,
+}
+
+/// A shadow of the QueryValue type.
+pub enum ShadowQueryValue {
+    Exact(String),
+    Capture(ShadowCaptureVariant),
+    OptionalCapture(ShadowCaptureVariant),
+    DefaultedCapture {
+        capture: ShadowCaptureVariant,
+        default: String,
+    },
+}
+
+impl ShadowQueryValue {
+    /// Gets the capture variant, if the value is captured.
+    pub fn capture_mut(&mut self) -> Option<&mut ShadowCaptureVariant> {
+        match self {
+            ShadowQueryValue::Exact(_) => None,
+            ShadowQueryValue::Capture(capture)
+            | ShadowQueryValue::OptionalCapture(capture)
+            | ShadowQueryValue::DefaultedCapture { capture, .. } => Some(capture),
+        }
+    }
+}
+
+impl ToTokens for ShadowQueryParam {
+    fn to_tokens(&self, ts: &mut TokenStream) {
+        let key = &self.key;
+        let value = match &self.value {
+            ShadowQueryValue::Exact(s) => quote! {
+                ::yew_router::matcher::QueryValue::Exact(::std::borrow::Cow::Borrowed(#s))
+            },
+            ShadowQueryValue::Capture(variant) => quote! {
+                ::yew_router::matcher::QueryValue::Capture(#variant)
+            },
+            ShadowQueryValue::OptionalCapture(variant) => quote! {
+                ::yew_router::matcher::QueryValue::OptionalCapture(#variant)
+            },
+            ShadowQueryValue::DefaultedCapture { capture, default } => quote! {
+                ::yew_router::matcher::QueryValue::DefaultedCapture {
+                    capture: #capture,
+                    default: ::std::borrow::Cow::Borrowed(#default),
+                }
+            },
+        };
+        ts.extend(quote! {
+            ::yew_router::matcher::QueryParam {
+                key: ::std::borrow::Cow::Borrowed(#key),
+                value: #value,
+            }
+        })
+    }
 }
 
 pub enum ShadowCaptureVariant {
@@ -44,19 +118,97 @@ pub enum ShadowCaptureVariant {
     ManyNamed(String),
     /// {2:name} - captures a fixed number of sections with a given name.
     NumberedNamed { sections: usize, name: String },
+    /// {name:u32} - captures a section with a given name, if it satisfies the constraint.
+    NamedConstrained {
+        name: String,
+        constraint: ShadowCaptureConstraint,
+    },
+}
+
+impl ShadowCaptureVariant {
+    /// The name that the capture will be stored under, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            ShadowCaptureVariant::Named(name)
+            | ShadowCaptureVariant::ManyNamed(name)
+            | ShadowCaptureVariant::NumberedNamed { name, .. }
+            | ShadowCaptureVariant::NamedConstrained { name, .. } => Some(name),
+            ShadowCaptureVariant::Unnamed
+            | ShadowCaptureVariant::ManyUnnamed
+            | ShadowCaptureVariant::NumberedUnnamed { .. } => None,
+        }
+    }
+
+    /// Gives the capture a new name, converting unnamed captures to their named equivalents.
+    pub fn rename(self, name: String) -> Self {
+        use ShadowCaptureVariant as SCV;
+        match self {
+            SCV::Unnamed | SCV::Named(_) => SCV::Named(name),
+            SCV::ManyUnnamed | SCV::ManyNamed(_) => SCV::ManyNamed(name),
+            SCV::NumberedUnnamed { sections } | SCV::NumberedNamed { sections, .. } => {
+                SCV::NumberedNamed { sections, name }
+            }
+            SCV::NamedConstrained { constraint, .. } => SCV::NamedConstrained { name, constraint },
+        }
+    }
+}
+
+/// A shadow of the CaptureConstraint type.
+pub enum ShadowCaptureConstraint {
+    Type(CaptureType),
+    Pattern(CharClass),
+}
+
+impl ToTokens for ShadowCaptureConstraint {
+    fn to_tokens(&self, ts: &mut TokenStream) {
+        let t = match self {
+            ShadowCaptureConstraint::Type(ty) => {
+                // The Debug representation is the name of the variant.
+                let ty = Ident::new(&format!("{:?}", ty), Span::call_site());
+                quote! {
+                    ::yew_router::matcher::CaptureConstraint::Type(
+                        ::yew_router::matcher::CaptureType::#ty
+                    )
+                }
+            }
+            ShadowCaptureConstraint::Pattern(CharClass {
+                negated,
+                ranges,
+                min,
+                max,
+            }) => {
+                let ranges = ranges.iter().map(|(start, end)| quote! {(#start, #end)});
+                let max = match max {
+                    Some(max) => quote! {::std::option::Option::Some(#max)},
+                    None => quote! {::std::option::Option::None},
+                };
+                quote! {
+                    ::yew_router::matcher::CaptureConstraint::Pattern(
+                        ::yew_router::matcher::CharClass {
+                            negated: #negated,
+                            ranges: ::std::borrow::Cow::Borrowed(&[#(#ranges),*]),
+                            min: #min,
+                            max: #max,
+                        }
+                    )
+                }
+            }
+        };
+        ts.extend(t)
+    }
 }
 
 impl ToTokens for ShadowCaptureVariant {
     fn to_tokens(&self, ts: &mut TokenStream) {
         let t = match self {
             ShadowCaptureVariant::Named(name) => {
-                quote! {::yew_router::matcher::CaptureVariant::Named(#name.to_string())}
+                quote! {::yew_router::matcher::CaptureVariant::Named(::std::borrow::Cow::Borrowed(#name))}
             }
             ShadowCaptureVariant::ManyNamed(name) => {
-                quote! {::yew_router::matcher::CaptureVariant::ManyNamed(#name.to_string())}
+                quote! {::yew_router::matcher::CaptureVariant::ManyNamed(::std::borrow::Cow::Borrowed(#name))}
             }
             ShadowCaptureVariant::NumberedNamed { sections, name } => {
-                quote! {::yew_router::matcher::CaptureVariant::NumberedNamed{sections: #sections, name: #name.to_string()}}
+                quote! {::yew_router::matcher::CaptureVariant::NumberedNamed{sections: #sections, name: ::std::borrow::Cow::Borrowed(#name)}}
             }
             ShadowCaptureVariant::Unnamed => {
                 quote! {::yew_router::matcher::CaptureVariant::Unnamed}
@@ -67,6 +219,9 @@ impl ToTokens for ShadowCaptureVariant {
             ShadowCaptureVariant::NumberedUnnamed { sections } => {
                 quote! {::yew_router::matcher::CaptureVariant::NumberedUnnamed{sections: #sections}}
             }
+            ShadowCaptureVariant::NamedConstrained { name, constraint } => {
+                quote! {::yew_router::matcher::CaptureVariant::NamedConstrained{name: ::std::borrow::Cow::Borrowed(#name), constraint: #constraint}}
+            }
         };
         ts.extend(t)
     }
@@ -77,9 +232,47 @@ impl From<MatcherToken> for ShadowMatcherToken {
         use MatcherToken as MT;
         use ShadowMatcherToken as SOT;
         match mt {
-            MT::Exact(s) => SOT::Exact(s),
+            MT::Exact(s) => SOT::Exact(s.into_owned()),
+            MT::Alternation(alternatives) => SOT::Alternation(
+                alternatives
+                    .iter()
+                    .map(|alternative| alternative.to_string())
+                    .collect(),
+            ),
             MT::Capture(capture) => SOT::Capture(capture.into()),
             MT::End => SOT::End,
+            MT::Query(params) => SOT::Query(
+                params
+                    .into_owned()
+                    .into_iter()
+                    .map(ShadowQueryParam::from)
+                    .collect(),
+            ),
+            MT::Optional(tokens) => {
+                SOT::Optional(tokens.into_owned().into_iter().map(SOT::from).collect())
+            }
+        }
+    }
+}
+
+impl From<QueryParam> for ShadowQueryParam {
+    fn from(param: QueryParam) -> Self {
+        let value = match param.value {
+            QueryValue::Exact(s) => ShadowQueryValue::Exact(s.into_owned()),
+            QueryValue::Capture(capture) => ShadowQueryValue::Capture(capture.into()),
+            QueryValue::OptionalCapture(capture) => {
+                ShadowQueryValue::OptionalCapture(capture.into())
+            }
+            QueryValue::DefaultedCapture { capture, default } => {
+                ShadowQueryValue::DefaultedCapture {
+                    capture: capture.into(),
+                    default: default.into_owned(),
+                }
+            }
+        };
+        ShadowQueryParam {
+            key: param.key.into_owned(),
+            value,
         }
     }
 }
@@ -88,14 +281,28 @@ impl From<CaptureVariant> for ShadowCaptureVariant {
     fn from(cv: CaptureVariant) -> Self {
         use ShadowCaptureVariant as SCV;
         match cv {
-            CaptureVariant::Named(name) => SCV::Named(name),
-            CaptureVariant::ManyNamed(name) => SCV::ManyNamed(name),
-            CaptureVariant::NumberedNamed { sections, name } => {
-                SCV::NumberedNamed { sections, name }
-            }
+            CaptureVariant::Named(name) => SCV::Named(name.into_owned()),
+            CaptureVariant::ManyNamed(name) => SCV::ManyNamed(name.into_owned()),
+            CaptureVariant::NumberedNamed { sections, name } => SCV::NumberedNamed {
+                sections,
+                name: name.into_owned(),
+            },
             CaptureVariant::Unnamed => SCV::Unnamed,
             CaptureVariant::ManyUnnamed => SCV::ManyUnnamed,
             CaptureVariant::NumberedUnnamed { sections } => SCV::NumberedUnnamed { sections },
+            CaptureVariant::NamedConstrained { name, constraint } => SCV::NamedConstrained {
+                name: name.into_owned(),
+                constraint: constraint.into(),
+            },
+        }
+    }
+}
+
+impl From<CaptureConstraint> for ShadowCaptureConstraint {
+    fn from(cc: CaptureConstraint) -> Self {
+        match cc {
+            CaptureConstraint::Type(ty) => ShadowCaptureConstraint::Type(ty),
+            CaptureConstraint::Pattern(class) => ShadowCaptureConstraint::Pattern(class),
         }
     }
 }
@@ -0,0 +1,101 @@
+//! Ranks matchers by how specific they are, so that more specific variants can be tried first.
+use crate::switch::{
+    shadow::{ShadowCaptureVariant, ShadowMatcherToken, ShadowQueryValue},
+    SwitchItem,
+};
+use std::cmp::{Ordering, Reverse};
+
+/// Measures of how specific a matcher is, ordered from most to least significant.
+///
+/// Sections within optional sections are ignored, because they might not be present.
+#[derive(Default, PartialEq, Eq)]
+struct Specificity {
+    /// Segments of the path, and query parameters, that are matched by literals.
+    literals: usize,
+    /// Sections that are captured, whether constrained or not.
+    captures: usize,
+    /// Captures that have a constraint.
+    constrained: usize,
+    /// Captures that can capture any number of sections.
+    wildcards: usize,
+    /// If the matcher has to consume the whole route.
+    end: bool,
+}
+
+impl Ord for Specificity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (
+            self.literals,
+            self.captures,
+            self.constrained,
+            Reverse(self.wildcards),
+            self.end,
+        )
+            .cmp(&(
+                other.literals,
+                other.captures,
+                other.constrained,
+                Reverse(other.wildcards),
+                other.end,
+            ))
+    }
+}
+
+impl PartialOrd for Specificity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Specificity {
+    fn of(tokens: &[ShadowMatcherToken]) -> Self {
+        let mut specificity = Specificity::default();
+        for token in tokens {
+            match token {
+                ShadowMatcherToken::Exact(literal) => {
+                    specificity.literals += literal
+                        .split(|c| c == '/' || c == '#')
+                        .filter(|segment| !segment.is_empty())
+                        .count()
+                }
+                ShadowMatcherToken::Alternation(_) => specificity.literals += 1,
+                ShadowMatcherToken::Capture(capture) => specificity.add_capture(capture),
+                ShadowMatcherToken::End => specificity.end = true,
+                ShadowMatcherToken::Query(params) => {
+                    for param in params {
+                        match &param.value {
+                            ShadowQueryValue::Exact(_) => specificity.literals += 1,
+                            ShadowQueryValue::Capture(capture) => specificity.add_capture(capture),
+                            ShadowQueryValue::OptionalCapture(_)
+                            | ShadowQueryValue::DefaultedCapture { .. } => {}
+                        }
+                    }
+                }
+                ShadowMatcherToken::Optional(_) => {}
+            }
+        }
+        specificity
+    }
+
+    fn add_capture(&mut self, capture: &ShadowCaptureVariant) {
+        match capture {
+            ShadowCaptureVariant::Named(_) | ShadowCaptureVariant::Unnamed => self.captures += 1,
+            ShadowCaptureVariant::NamedConstrained { .. } => {
+                self.captures += 1;
+                self.constrained += 1;
+            }
+            ShadowCaptureVariant::NumberedNamed { sections, .. }
+            | ShadowCaptureVariant::NumberedUnnamed { sections } => self.captures += sections,
+            ShadowCaptureVariant::ManyNamed(_) | ShadowCaptureVariant::ManyUnnamed => {
+                self.wildcards += 1
+            }
+        }
+    }
+}
+
+/// Sorts the items from most to least specific.
+///
+/// The sort is stable, so items that are equally specific keep their declaration order.
+pub fn sort_by_specificity(items: &mut [SwitchItem]) {
+    items.sort_by_cached_key(|item| Reverse(Specificity::of(&item.matcher)))
+}
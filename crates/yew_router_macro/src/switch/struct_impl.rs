@@ -1,4 +1,5 @@
 pub use self::{build_route_section::BuildRouteSection, from_route_part::FromRoutePart};
+use crate::switch::{attribute::TrailingSlash, explain::Explain, routes::Routes};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 
@@ -9,6 +10,9 @@ mod from_route_part;
 pub struct StructInner<'a> {
     pub from_route_part: FromRoutePart<'a>,
     pub build_route_section: BuildRouteSection<'a>,
+    pub explain: Explain<'a>,
+    pub routes: Routes<'a>,
+    pub trailing_slash: TrailingSlash,
 }
 
 impl<'a> ToTokens for StructInner<'a> {
@@ -16,10 +20,19 @@ impl<'a> ToTokens for StructInner<'a> {
         let StructInner {
             from_route_part,
             build_route_section,
+            explain,
+            routes,
+            trailing_slash,
         } = self;
         tokens.extend(quote! {
              #from_route_part
              #build_route_section
+             #explain
+             #routes
+
+             fn trailing_slash() -> ::yew_router::matcher::TrailingSlash {
+                 #trailing_slash
+             }
         })
     }
 }
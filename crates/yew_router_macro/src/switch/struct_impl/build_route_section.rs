@@ -1,6 +1,4 @@
-use crate::switch::{
-    shadow::ShadowMatcherToken, unnamed_field_index_item, write_for_token, FieldType, SwitchItem,
-};
+use crate::switch::{capture_fields, unnamed_field_index_item, write_for_tokens, SwitchItem};
 use proc_macro2::{Ident, TokenStream};
 use quote::{quote, ToTokens};
 use syn::Fields;
@@ -27,19 +25,18 @@ pub fn build_serializer_for_struct(switch_item: &SwitchItem, item: &Ident) -> To
         matcher,
         ident,
         fields,
+        ..
     } = switch_item;
+    let writers = write_for_tokens(matcher, &capture_fields(fields));
     let destructor_and_writers = match fields {
         Fields::Named(fields_named) => {
             let field_names = fields_named
                 .named
                 .iter()
                 .filter_map(|named| named.ident.as_ref());
-            let writers = matcher
-                .iter()
-                .map(|token| write_for_token(token, FieldType::Named));
             quote! {
                 let #ident{#(#field_names),*} = #item;
-                #(#writers)*
+                #writers
             }
         }
         Fields::Unnamed(fields_unnamed) => {
@@ -48,28 +45,14 @@ pub fn build_serializer_for_struct(switch_item: &SwitchItem, item: &Ident) -> To
                 .iter()
                 .enumerate()
                 .map(|(index, _)| unnamed_field_index_item(index));
-            let mut item_count = 0;
-            let writers = matcher.iter().map(|token| {
-                if let ShadowMatcherToken::Capture(_) = &token {
-                    let ts = write_for_token(token, FieldType::Unnamed { index: item_count });
-                    item_count += 1;
-                    ts
-                } else {
-                    // Its either a literal, or something that will panic currently
-                    write_for_token(token, FieldType::Unit)
-                }
-            });
             quote! {
                 let #ident(#(#field_names),*) = #item;
-                #(#writers)*
+                #writers
             }
         }
         Fields::Unit => {
-            let writers = matcher
-                .iter()
-                .map(|token| write_for_token(token, FieldType::Unit));
             quote! {
-                #(#writers)*
+                #writers
             }
         }
     };
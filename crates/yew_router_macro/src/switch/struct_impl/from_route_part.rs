@@ -1,5 +1,7 @@
 // use crate::switch::{SwitchItem, write_for_token, FieldType, unnamed_field_index_item};
-use crate::switch::SwitchItem;
+use crate::switch::{
+    build_field_from_capture, query_captures, shadow::ShadowMatcherToken, SwitchItem,
+};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{Field, Fields, Type};
@@ -13,17 +15,24 @@ impl<'a> ToTokens for FromRoutePart<'a> {
             matcher,
             ident,
             fields,
+            settings,
+            ..
         } = &self.0;
 
-        let matcher = super::super::build_matcher_from_tokens(&matcher);
-        let build_from_captures = build_struct_from_captures(ident, fields);
+        let build_from_captures = build_struct_from_captures(ident, fields, matcher);
+        let matcher = super::super::build_matcher_from_tokens(&matcher, settings);
 
         tokens.extend(quote! {
             fn from_route_part<__T>(
-                route: String, mut state: Option<__T>
+                route: String, state: Option<__T>
+            ) -> (::std::option::Option<Self>, ::std::option::Option<__T>) {
+                Self::from_route_part_ref(&route, state)
+            }
+
+            fn from_route_part_ref<__T>(
+                route: &str, mut state: Option<__T>
             ) -> (::std::option::Option<Self>, ::std::option::Option<__T>) {
                 #matcher
-                let route_string = route;
 
                 #build_from_captures
 
@@ -33,7 +42,12 @@ impl<'a> ToTokens for FromRoutePart<'a> {
     }
 }
 
-fn build_struct_from_captures(ident: &Ident, fields: &Fields) -> TokenStream {
+fn build_struct_from_captures(
+    ident: &Ident,
+    fields: &Fields,
+    matcher: &[ShadowMatcherToken],
+) -> TokenStream {
+    let query_captures = query_captures(matcher);
     match fields {
         Fields::Named(named_fields) => {
             let (field_declarations, fields): (Vec<_>, Vec<_>) = named_fields
@@ -47,30 +61,10 @@ fn build_struct_from_captures(ident: &Ident, fields: &Fields) -> TokenStream {
                     })
                 })
                 .map(|(field_name, key, field_ty): (&Ident, String, &Type)| {
+                    let field_from_capture =
+                        build_field_from_capture(&key, field_ty, query_captures.contains(&key));
                     let field_decl = quote! {
-                        let #field_name = {
-                            let (v, s) = match captures.remove(#key) {
-                                ::std::option::Option::Some(value) => {
-                                    <#field_ty as ::yew_router::Switch>::from_route_part(
-                                        value,
-                                        state,
-                                    )
-                                }
-                                ::std::option::Option::None => {
-                                    (
-                                        <#field_ty as ::yew_router::Switch>::key_not_available(),
-                                        state,
-                                    )
-                                }
-                            };
-                            match v {
-                                ::std::option::Option::Some(val) => {
-                                    state = s; // Set state for the next var.
-                                    val
-                                },
-                                ::std::option::Option::None => return (::std::option::Option::None, s) // Failed
-                            }
-                        };
+                        let #field_name = #field_from_capture;
                     };
 
                     (field_decl, field_name)
@@ -79,7 +73,7 @@ fn build_struct_from_captures(ident: &Ident, fields: &Fields) -> TokenStream {
 
             quote! {
                 if let ::std::option::Option::Some(mut captures) = matcher
-                    .capture_route_into_map(&route_string)
+                    .capture_route_into_ref_map(route)
                     .ok()
                     .map(|x| x.1)
                 {
@@ -104,30 +98,13 @@ fn build_struct_from_captures(ident: &Ident, fields: &Fields) -> TokenStream {
                 .map(|(idx, f)| {
                     let field_ty = &f.ty;
                     let field_var_name = Ident::new(&format!("field_{}", idx), Span::call_site());
+                    let field_from_capture = build_field_from_capture(
+                        &idx.to_string(),
+                        field_ty,
+                        query_captures.contains(&idx.to_string()),
+                    );
                     let field_decl = quote! {
-                        let #field_var_name = {
-                            let (v, s) = match drain.next() {
-                                ::std::option::Option::Some(value) => {
-                                    <#field_ty as ::yew_router::Switch>::from_route_part(
-                                        value,
-                                        state,
-                                    )
-                                },
-                                ::std::option::Option::None => {
-                                    (
-                                        <#field_ty as ::yew_router::Switch>::key_not_available(),
-                                        state,
-                                    )
-                                }
-                            };
-                            match v {
-                                ::std::option::Option::Some(val) => {
-                                    state = s; // Set state for the next var.
-                                    val
-                                },
-                                ::std::option::Option::None => return (::std::option::Option::None, s) // Failed
-                            }
-                        };
+                        let #field_var_name = #field_from_capture;
                     };
 
                     (field_decl, field_var_name)
@@ -135,8 +112,7 @@ fn build_struct_from_captures(ident: &Ident, fields: &Fields) -> TokenStream {
                 .unzip();
 
             quote! {
-                if let Some(mut captures) = matcher.capture_route_into_vec(&route_string).ok().map(|x| x.1) {
-                    let mut drain = captures.drain(..);
+                if let Some(mut captures) = matcher.capture_route_into_ref_map(route).ok().map(|x| x.1) {
                     #(#field_declarations)*
 
                     return (
@@ -152,7 +128,7 @@ fn build_struct_from_captures(ident: &Ident, fields: &Fields) -> TokenStream {
         }
         Fields::Unit => {
             return quote! {
-                let mut state = if let ::std::option::Option::Some(_captures) = matcher.capture_route_into_map(&route_string).ok().map(|x| x.1) {
+                let mut state = if let ::std::option::Option::Some(_captures) = matcher.capture_route_into_ref_map(route).ok().map(|x| x.1) {
                     return (::std::option::Option::Some(#ident), state);
                 } else {
                     state
@@ -1,7 +1,7 @@
 use crate::{
     error::{ExpectedToken, ParserErrorReason},
-    parser::{CaptureOrExact, RefCaptureVariant, RouteParserToken},
-    ParseError,
+    parser::{CaptureOrExact, RefCaptureConstraint, RefCaptureVariant, RouteParserToken},
+    CaptureType, CharClass, ParseError,
 };
 use nom::{
     branch::alt,
@@ -10,9 +10,9 @@ use nom::{
         complete::{char, digit1},
         is_digit,
     },
-    combinator::{map, map_parser},
+    combinator::{map, map_parser, opt, recognize},
     error::ErrorKind,
-    sequence::{delimited, separated_pair},
+    sequence::{delimited, pair, preceded, separated_pair},
     IResult,
 };
 
@@ -56,6 +56,20 @@ pub fn get_end(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
         .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::End)))
 }
 
+/// Returns an OptionalBegin variant if the next character is a '['.
+pub fn get_open_optional(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
+    map(char('['), |_: char| RouteParserToken::OptionalBegin)(i).map_err(|_: nom::Err<()>| {
+        nom::Err::Error(ParseError::expected(ExpectedToken::OpenOptional))
+    })
+}
+
+/// Returns an OptionalEnd variant if the next character is a ']'.
+pub fn get_close_optional(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
+    map(char(']'), |_: char| RouteParserToken::OptionalEnd)(i).map_err(|_: nom::Err<()>| {
+        nom::Err::Error(ParseError::expected(ExpectedToken::CloseOptional))
+    })
+}
+
 /// Returns an End variant if the next character is a '!`.
 fn get_open_bracket(i: &str) -> IResult<&str, (), ParseError> {
     map(char('{'), |_: char| ())(i).map_err(|_: nom::Err<()>| {
@@ -84,35 +98,50 @@ fn get_colon(i: &str) -> IResult<&str, (), ParseError> {
         .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::Colon)))
 }
 
+fn get_pipe(i: &str) -> IResult<&str, (), ParseError> {
+    map(char('|'), |_: char| ())(i)
+        .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::Pipe)))
+}
+
+fn get_open_paren(i: &str) -> IResult<&str, (), ParseError> {
+    map(char('('), |_: char| ())(i)
+        .map_err(|_: nom::Err<()>| nom::Err::Error(ParseError::expected(ExpectedToken::OpenParen)))
+}
+
 fn rust_ident(i: &str) -> IResult<&str, &str, ParseError> {
     let invalid_ident_chars = r##" \|/{[]()?+=-!@#$%^&*~`'";:"##;
-    // Detect an ident by first reading until a } is found,
+    // Detect an ident by first reading until a } (or the start of a constraint or default) is found,
     // then validating the captured section against invalid characters that can't be in rust idents.
-    map_parser(take_till1(move |c| c == '}'), move |i: &str| {
-        match take_till1::<_, _, ()>(|c| invalid_ident_chars.contains(c))(i) {
-            Ok((remain, got)) => {
-                // Detects if the first character is a digit.
-                if !got.is_empty() && got.starts_with(|c: char| is_digit(c as u8)) {
-                    Err(nom::Err::Failure(ParseError {
-                        reason: Some(ParserErrorReason::BadRustIdent(got.chars().next().unwrap())),
-                        expected: vec![ExpectedToken::Ident],
-                        offset: 1,
-                    }))
-                } else if !remain.is_empty() {
-                    Err(nom::Err::Failure(ParseError {
-                        reason: Some(ParserErrorReason::BadRustIdent(
-                            remain.chars().next().unwrap(),
-                        )),
-                        expected: vec![ExpectedToken::CloseBracket, ExpectedToken::Ident],
-                        offset: got.len() + 1,
-                    }))
-                } else {
-                    Ok((i, i))
+    map_parser(
+        take_till1(move |c| c == '}' || c == ':' || c == '|' || c == '='),
+        move |i: &str| {
+            match take_till1::<_, _, ()>(|c| invalid_ident_chars.contains(c))(i) {
+                Ok((remain, got)) => {
+                    // Detects if the first character is a digit.
+                    if !got.is_empty() && got.starts_with(|c: char| is_digit(c as u8)) {
+                        Err(nom::Err::Failure(ParseError {
+                            reason: Some(ParserErrorReason::BadRustIdent(
+                                got.chars().next().unwrap(),
+                            )),
+                            expected: vec![ExpectedToken::Ident],
+                            offset: 1,
+                        }))
+                    } else if !remain.is_empty() {
+                        Err(nom::Err::Failure(ParseError {
+                            reason: Some(ParserErrorReason::BadRustIdent(
+                                remain.chars().next().unwrap(),
+                            )),
+                            expected: vec![ExpectedToken::CloseBracket, ExpectedToken::Ident],
+                            offset: got.len() + 1,
+                        }))
+                    } else {
+                        Ok((i, i))
+                    }
                 }
+                Err(_) => Ok((i, i)),
             }
-            Err(_) => Ok((i, i)),
-        }
-    })(i)
+        },
+    )(i)
 }
 
 /// Matches escaped items
@@ -166,10 +195,42 @@ fn exact_impl(special_chars: &'static str) -> impl Fn(&str) -> IResult<&str, &st
 }
 
 const SPECIAL_CHARS: &str = r##"/?&#={}!"##;
+/// Square brackets delimit optional sections, and parentheses delimit alternations,
+/// so they can't appear as literals within the path.
+const PATH_SPECIAL_CHARS: &str = r##"/?&#={}![]()"##;
 const FRAGMENT_SPECIAL_CHARS: &str = r##"{}!"##;
 
 pub fn exact(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
-    map(exact_impl(SPECIAL_CHARS), RouteParserToken::Exact)(i)
+    map(exact_impl(PATH_SPECIAL_CHARS), RouteParserToken::Exact)(i)
+}
+
+/// Matches a group of literal alternatives - `(en|de|fr)`.
+///
+/// Each alternative must be non-empty, and can't contain any characters that are special within
+/// the path.
+pub fn alternation(i: &str) -> IResult<&str, RouteParserToken, ParseError> {
+    let (inner, _) = get_open_paren(i)?;
+    let failure = |offset: usize| {
+        nom::Err::Failure(ParseError {
+            reason: Some(ParserErrorReason::BadAlternation),
+            expected: vec![ExpectedToken::Literal, ExpectedToken::CloseParen],
+            offset,
+        })
+    };
+    let end = inner.find(')').ok_or_else(|| failure(i.len()))?;
+    let alternatives = &inner[..end];
+
+    let mut offset = 1;
+    for alternative in alternatives.split('|') {
+        if alternative.is_empty() || alternative.contains(|c| PATH_SPECIAL_CHARS.contains(c)) {
+            return Err(failure(offset));
+        }
+        offset += alternative.len() + 1;
+    }
+    Ok((
+        &inner[end + 1..],
+        RouteParserToken::Alternation(alternatives),
+    ))
 }
 
 /// More permissive exact matchers
@@ -244,10 +305,139 @@ fn capture_impl<'a>(
     }
 }
 
+/// Matches the name of a primitive type that a capture can be constrained to.
+fn capture_type(i: &str) -> IResult<&str, CaptureType, ParseError> {
+    let (remain, name) = take_till1::<_, _, ()>(|c| c == '}' || c == '=')(i)
+        .map_err(|_| nom::Err::Failure(ParseError::expected(ExpectedToken::CaptureType)))?;
+    match CaptureType::from_name(name) {
+        Some(ty) => Ok((remain, ty)),
+        None => Err(nom::Err::Failure(ParseError {
+            reason: Some(ParserErrorReason::UnknownCaptureType),
+            expected: vec![ExpectedToken::CaptureType],
+            offset: 0,
+        })),
+    }
+}
+
+/// Matches a character class like `[a-z0-9_]+`.
+///
+/// Within the brackets, a leading `^` negates the class, `-` between two characters denotes a range,
+/// and `\` escapes the next character.
+/// The class may be followed by one of the `+`, `*`, or `?` quantifiers,
+/// and if none is present, exactly one character is matched.
+pub(crate) fn char_class(i: &str) -> IResult<&str, CharClass, ParseError> {
+    let bad_class = |offset: usize| {
+        nom::Err::Failure(ParseError {
+            reason: Some(ParserErrorReason::BadCharClass),
+            expected: vec![ExpectedToken::CharClass],
+            offset,
+        })
+    };
+
+    let mut chars = i.char_indices().peekable();
+    match chars.next() {
+        Some((_, '[')) => {}
+        _ => return Err(bad_class(0)),
+    }
+    let negated = chars.peek().map(|(_, c)| *c) == Some('^');
+    if negated {
+        chars.next();
+    }
+
+    let mut ranges: Vec<(char, char)> = vec![];
+    let end = loop {
+        let (index, c) = chars.next().ok_or_else(|| bad_class(i.len()))?;
+        let start = match c {
+            ']' => break index + 1,
+            '{' | '}' => return Err(bad_class(index)),
+            '\\' => chars.next().ok_or_else(|| bad_class(i.len()))?.1,
+            c => c,
+        };
+        // A '-' that is not the last character in the class denotes a range.
+        let mut lookahead = chars.clone();
+        if let (Some((_, '-')), Some((_, end))) = (lookahead.next(), lookahead.next()) {
+            if end != ']' {
+                chars.next();
+                let (index, end) = match chars.next() {
+                    Some((_, '\\')) => chars.next().ok_or_else(|| bad_class(i.len()))?,
+                    Some(next) => next,
+                    None => return Err(bad_class(i.len())),
+                };
+                if end < start || end == '{' || end == '}' {
+                    return Err(bad_class(index));
+                }
+                ranges.push((start, end));
+                continue;
+            }
+        }
+        ranges.push((start, start));
+    };
+
+    if ranges.is_empty() {
+        return Err(bad_class(end));
+    }
+
+    let remain = &i[end..];
+    let (remain, (min, max)) = match remain.chars().next() {
+        Some('+') => (&remain[1..], (1, None)),
+        Some('*') => (&remain[1..], (0, None)),
+        Some('?') => (&remain[1..], (0, Some(1))),
+        _ => (remain, (1, Some(1))),
+    };
+
+    Ok((
+        remain,
+        CharClass {
+            negated,
+            ranges: ranges.into(),
+            min,
+            max,
+        },
+    ))
+}
+
+/// Matches `:type` or `|[class]` after the name of a capture.
+fn capture_constraint(i: &str) -> IResult<&str, RefCaptureConstraint, ParseError> {
+    alt((
+        map(
+            preceded(get_colon, capture_type),
+            RefCaptureConstraint::Type,
+        ),
+        map(
+            preceded(get_pipe, recognize(char_class)),
+            RefCaptureConstraint::Pattern,
+        ),
+    ))(i)
+    .map_err(|e| match e {
+        // Account for the leading ':' or '|'.
+        nom::Err::Failure(mut e) => {
+            e.offset += 1;
+            nom::Err::Failure(e)
+        }
+        e => e,
+    })
+}
+
 mod named {
     use super::*;
     pub fn single_capture_impl(i: &str) -> IResult<&str, RefCaptureVariant, ParseError> {
-        map(rust_ident, |key| RefCaptureVariant::Named(key))(i)
+        let (i, key) = rust_ident(i)?;
+        let (i, constraint) = opt(capture_constraint)(i).map_err(|e| match e {
+            // Account for the leading '{' and the ident.
+            nom::Err::Failure(mut e) => {
+                e.offset += 1 + key.len();
+                nom::Err::Failure(e)
+            }
+            e => e,
+        })?;
+        let capture = match constraint {
+            Some(constraint) => RefCaptureVariant::NamedConstrained {
+                name: key,
+                constraint,
+            },
+            None => RefCaptureVariant::Named(key),
+        };
+        Ok((i, capture))
     }
 
     pub fn many_capture_impl(i: &str) -> IResult<&str, RefCaptureVariant, ParseError> {
@@ -288,15 +478,47 @@ mod unnamed {
     }
 }
 
+/// Matches a capture with a default value - {ident=default}.
+fn defaulted_capture_impl<'a>(
+    field_naming_scheme: FieldNamingScheme,
+) -> impl Fn(&'a str) -> IResult<&'a str, (RefCaptureVariant<'a>, &'a str), ParseError> {
+    move |i: &str| match field_naming_scheme {
+        FieldNamingScheme::Named | FieldNamingScheme::Unnamed => delimited(
+            get_open_bracket,
+            separated_pair(
+                named::single_capture_impl,
+                get_eq,
+                exact_impl(SPECIAL_CHARS),
+            ),
+            get_close_bracket,
+        )(i),
+        // Let the single capture parser report that captures aren't allowed.
+        FieldNamingScheme::Unit => Err(nom::Err::Error(ParseError {
+            reason: Some(ParserErrorReason::CapturesInUnit),
+            expected: vec![],
+            offset: 0,
+        })),
+    }
+}
+
 /// Gets a capture or exact, mapping it to the CaptureOrExact enum - to provide a limited subset.
+///
+/// Captures may be marked as optional with a trailing `?`, or be given a default - `{ident=default}`.
 fn cap_or_exact<'a>(
     field_naming_scheme: FieldNamingScheme,
 ) -> impl Fn(&'a str) -> IResult<&'a str, CaptureOrExact<'a>, ParseError> {
     move |i: &str| {
         alt((
             map(
-                capture_single_impl(field_naming_scheme),
-                CaptureOrExact::Capture,
+                defaulted_capture_impl(field_naming_scheme),
+                |(capture, default)| CaptureOrExact::DefaultedCapture { capture, default },
+            ),
+            map(
+                pair(capture_single_impl(field_naming_scheme), opt(get_question)),
+                |(capture, optional)| match optional {
+                    Some(_) => CaptureOrExact::OptionalCapture(capture),
+                    None => CaptureOrExact::Capture(capture),
+                },
             ),
             map(exact_impl(SPECIAL_CHARS), CaptureOrExact::Exact),
         ))(i)
@@ -367,4 +589,44 @@ mod test {
     fn leading_numbers_in_ident_fails() {
         rust_ident("5hello").expect_err("sholud not parse");
     }
+
+    #[test]
+    fn char_class_ranges() {
+        let (remain, class) = char_class("[a-z_0-9]+}").expect("should parse");
+        assert_eq!(remain, "}");
+        assert_eq!(
+            class,
+            CharClass {
+                negated: false,
+                ranges: vec![('a', 'z'), ('_', '_'), ('0', '9')].into(),
+                min: 1,
+                max: None
+            }
+        );
+        assert!(class.is_satisfied_by("lorem_42"));
+        assert!(!class.is_satisfied_by("Lorem"));
+        assert!(!class.is_satisfied_by(""));
+    }
+
+    #[test]
+    fn char_class_negated_with_escapes() {
+        let (_, class) = char_class(r"[^\]\-]*").expect("should parse");
+        assert!(class.negated);
+        assert_eq!(class.ranges, &[(']', ']'), ('-', '-')][..]);
+        assert!(class.is_satisfied_by(""));
+        assert!(class.is_satisfied_by("lorem"));
+        assert!(!class.is_satisfied_by("lor-em"));
+    }
+
+    #[test]
+    fn char_class_without_quantifier_matches_one() {
+        let (_, class) = char_class("[xyz]").expect("should parse");
+        assert!(class.is_satisfied_by("x"));
+        assert!(!class.is_satisfied_by("xy"));
+    }
+
+    #[test]
+    fn empty_char_class_fails() {
+        char_class("[]").expect_err("should not parse");
+    }
 }
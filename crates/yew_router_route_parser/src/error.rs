@@ -17,6 +17,13 @@ fn offset(input: &str, substring: &str) -> usize {
     input.len() - substring.len()
 }
 
+impl<'a> PrettyParseError<'a> {
+    /// The byte offset within the input where the error was encountered.
+    pub fn offset(&self) -> usize {
+        offset(self.input, self.remaining) + self.error.offset
+    }
+}
+
 impl<'a> fmt::Debug for PrettyParseError<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("Could not parse route.")?;
@@ -27,8 +34,11 @@ impl<'a> fmt::Debug for PrettyParseError<'a> {
         f.write_str(self.input)?;
         f.write_str("\n")?;
 
-        let offset = offset(self.input, self.remaining);
-        let offset = offset + self.error.offset;
+        // The caret is drawn under a character, so the padding counts characters instead of bytes.
+        let offset = self
+            .input
+            .get(..self.offset())
+            .map_or(self.offset(), |before| before.chars().count());
         let pad = (0..offset + route_str.len())
             .map(|_| '-')
             .collect::<String>();
@@ -134,6 +144,20 @@ pub enum ExpectedToken {
     Star,
     /// :
     Colon,
+    /// |
+    Pipe,
+    /// name of a primitive type within {}
+    CaptureType,
+    /// [...] character class within {}
+    CharClass,
+    /// (
+    OpenParen,
+    /// )
+    CloseParen,
+    /// [
+    OpenOptional,
+    /// ]
+    CloseOptional,
 }
 
 impl fmt::Display for ExpectedToken {
@@ -151,6 +175,13 @@ impl fmt::Display for ExpectedToken {
             ExpectedToken::Equals => f.write_str("="),
             ExpectedToken::Star => f.write_str("*"),
             ExpectedToken::Colon => f.write_str(":"),
+            ExpectedToken::Pipe => f.write_str("|"),
+            ExpectedToken::CaptureType => f.write_str("<type>"),
+            ExpectedToken::CharClass => f.write_str("[<characters>]"),
+            ExpectedToken::OpenParen => f.write_str("("),
+            ExpectedToken::CloseParen => f.write_str(")"),
+            ExpectedToken::OpenOptional => f.write_str("["),
+            ExpectedToken::CloseOptional => f.write_str("]"),
         }
     }
 }
@@ -178,6 +209,20 @@ pub enum ParserErrorReason {
     InvalidState,
     /// Can't have capture sections for unit structs/variants
     CapturesInUnit,
+    /// The type used to constrain a capture isn't a supported primitive type.
+    UnknownCaptureType,
+    /// The character class used to constrain a capture is malformed.
+    BadCharClass,
+    /// An alternation is unterminated, or contains an empty or invalid alternative.
+    BadAlternation,
+    /// An optional section was not closed before the end of the path.
+    UnclosedOptional,
+    /// A ] appears without a preceding [
+    UnmatchedOptionalEnd,
+    /// An optional section has nothing in it.
+    EmptyOptional,
+    /// Optional sections can't start with a capture, and captures can't directly follow them.
+    CaptureNextToOptional,
     /// Internal check on valid state transitions
     /// This should never actually be created.
     NotAllowedStateTransition,
@@ -227,6 +272,29 @@ impl fmt::Display for ParserErrorReason {
             ParserErrorReason::CapturesInUnit => {
                 f.write_str("Cannot have a capture section for a unit struct or variant.")?;
             }
+            ParserErrorReason::UnknownCaptureType => {
+                f.write_str(
+                    "Captures can only be constrained to integer, float, bool, or char types.",
+                )?;
+            }
+            ParserErrorReason::BadAlternation => {
+                f.write_str("Alternations must be closed with ')', and each alternative separated by '|' must be a non-empty literal.")?;
+            }
+            ParserErrorReason::UnclosedOptional => {
+                f.write_str("An optional section opened with '[' must be closed with ']' before the query, fragment, or end of the path.")?;
+            }
+            ParserErrorReason::UnmatchedOptionalEnd => {
+                f.write_str("A ']' appeared without a preceding '['.")?;
+            }
+            ParserErrorReason::EmptyOptional => {
+                f.write_str("Optional sections can't be empty.")?;
+            }
+            ParserErrorReason::CaptureNextToOptional => {
+                f.write_str("Optional sections can't start with a capture, and captures can't directly follow an optional section. There must be some character in between.")?;
+            }
+            ParserErrorReason::BadCharClass => {
+                f.write_str("Malformed character class. Expected something like `[a-z0-9_]+`.")?;
+            }
         }
         Ok(())
     }
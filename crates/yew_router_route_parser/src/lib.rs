@@ -20,7 +20,9 @@ pub use crate::core::FieldNamingScheme;
 pub use error::{ParseError, PrettyParseError};
 mod optimizer;
 pub use optimizer::{convert_tokens, parse_str_and_optimize_tokens};
-use std::collections::HashMap;
+mod trie;
+use std::{borrow::Cow, collections::HashMap};
+pub use trie::{KeyPart, PrefixTrie, TrieEdge, TrieNode};
 
 /// Alias of `HashMap<&'a str, String>` that represent strings captured from a route.
 ///
@@ -29,16 +31,65 @@ use std::collections::HashMap;
 pub type Captures<'a> = HashMap<&'a str, String>;
 
 /// Tokens used to determine how to match and capture sections from a URL.
+///
+/// Strings and lists are held in `Cow`s so that matchers can be declared as `static`s without
+/// allocating, while those produced by the parser own their contents.
 #[derive(Debug, PartialEq, Clone)]
 pub enum MatcherToken {
     /// Section-related tokens can be condensed into a match.
-    Exact(String),
+    Exact(Cow<'static, str>),
+    /// Match the first of the alternatives that matches.
+    Alternation(Cow<'static, [Cow<'static, str>]>),
     /// Capture section.
     Capture(CaptureVariant),
     /// End token - if the string hasn't been consumed entirely, then the parse will fail.
     /// This is useful for being able to specify more general matchers for variants that would
     /// otherwise match above more specific variants.
     End,
+    /// Query section - the parameters are matched regardless of the order they appear in.
+    Query(Cow<'static, [QueryParam]>),
+    /// Optional section - the matcher will try to match the contained tokens,
+    /// and if that fails, it will continue as if they were absent.
+    Optional(Cow<'static, [MatcherToken]>),
+}
+
+/// A key and the value it should match within the query section.
+#[derive(Debug, PartialEq, Clone)]
+pub struct QueryParam {
+    /// The key of the parameter.
+    pub key: Cow<'static, str>,
+    /// How the value of the parameter should be matched.
+    pub value: QueryValue,
+}
+
+/// Indicates how the value of a query parameter should be matched.
+#[derive(Debug, PartialEq, Clone)]
+pub enum QueryValue {
+    /// The value must match a specific string.
+    Exact(Cow<'static, str>),
+    /// The value is captured.
+    Capture(CaptureVariant),
+    /// The value is captured if the key is present, otherwise nothing is captured.
+    OptionalCapture(CaptureVariant),
+    /// The value is captured, or if the key is absent, the default is captured in its place.
+    DefaultedCapture {
+        /// The capture variant.
+        capture: CaptureVariant,
+        /// The value captured if the key is absent.
+        default: Cow<'static, str>,
+    },
+}
+
+impl QueryValue {
+    /// Gets the capture variant, if the value is captured.
+    pub fn capture(&self) -> Option<&CaptureVariant> {
+        match self {
+            QueryValue::Exact(_) => None,
+            QueryValue::Capture(capture)
+            | QueryValue::OptionalCapture(capture)
+            | QueryValue::DefaultedCapture { capture, .. } => Some(capture),
+        }
+    }
 }
 
 /// Variants that indicate how part of a string should be captured.
@@ -54,14 +105,169 @@ pub enum CaptureVariant {
         sections: usize,
     },
     /// {name} - captures a section and adds it to the map with a given name.
-    Named(String),
+    Named(Cow<'static, str>),
     /// {*:name} - captures over many sections and adds it to the map with a given name.
-    ManyNamed(String),
+    ManyNamed(Cow<'static, str>),
     /// {2:name} - captures a fixed number of sections with a given name.
     NumberedNamed {
         /// Number of sections to match.
         sections: usize,
         /// The key to be entered in the `Matches` map.
-        name: String,
+        name: Cow<'static, str>,
+    },
+    /// {name:u32} or {name|[0-9]+} - captures a section with a given name,
+    /// but only if the section satisfies the constraint.
+    NamedConstrained {
+        /// The key to be entered in the `Matches` map.
+        name: Cow<'static, str>,
+        /// The constraint that the captured section must satisfy.
+        constraint: CaptureConstraint,
     },
 }
+
+/// A restriction on what a capture section is allowed to match.
+///
+/// Constraints are checked when matching, so a section that doesn't satisfy the constraint causes
+/// the whole matcher to fail, allowing the next variant to be tried instead.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CaptureConstraint {
+    /// {name:u32} - the section must be parsable as the given type.
+    Type(CaptureType),
+    /// {name|[a-z0-9]+} - the section must consist of characters from the given class.
+    Pattern(CharClass),
+}
+
+impl CaptureConstraint {
+    /// Checks if the captured section satisfies the constraint.
+    pub fn is_satisfied_by(&self, section: &str) -> bool {
+        match self {
+            CaptureConstraint::Type(ty) => ty.is_satisfied_by(section),
+            CaptureConstraint::Pattern(class) => class.is_satisfied_by(section),
+        }
+    }
+}
+
+/// Primitive types that can be used to constrain a capture section.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[allow(missing_docs)]
+pub enum CaptureType {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+    F32,
+    F64,
+    Bool,
+    Char,
+}
+
+impl CaptureType {
+    /// Gets the type from its name as it would be written in Rust.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let ty = match name {
+            "u8" => CaptureType::U8,
+            "u16" => CaptureType::U16,
+            "u32" => CaptureType::U32,
+            "u64" => CaptureType::U64,
+            "u128" => CaptureType::U128,
+            "usize" => CaptureType::Usize,
+            "i8" => CaptureType::I8,
+            "i16" => CaptureType::I16,
+            "i32" => CaptureType::I32,
+            "i64" => CaptureType::I64,
+            "i128" => CaptureType::I128,
+            "isize" => CaptureType::Isize,
+            "f32" => CaptureType::F32,
+            "f64" => CaptureType::F64,
+            "bool" => CaptureType::Bool,
+            "char" => CaptureType::Char,
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    /// The name of the type as it would be written in Rust.
+    pub fn name(self) -> &'static str {
+        match self {
+            CaptureType::U8 => "u8",
+            CaptureType::U16 => "u16",
+            CaptureType::U32 => "u32",
+            CaptureType::U64 => "u64",
+            CaptureType::U128 => "u128",
+            CaptureType::Usize => "usize",
+            CaptureType::I8 => "i8",
+            CaptureType::I16 => "i16",
+            CaptureType::I32 => "i32",
+            CaptureType::I64 => "i64",
+            CaptureType::I128 => "i128",
+            CaptureType::Isize => "isize",
+            CaptureType::F32 => "f32",
+            CaptureType::F64 => "f64",
+            CaptureType::Bool => "bool",
+            CaptureType::Char => "char",
+        }
+    }
+
+    fn is_satisfied_by(self, section: &str) -> bool {
+        match self {
+            CaptureType::U8 => section.parse::<u8>().is_ok(),
+            CaptureType::U16 => section.parse::<u16>().is_ok(),
+            CaptureType::U32 => section.parse::<u32>().is_ok(),
+            CaptureType::U64 => section.parse::<u64>().is_ok(),
+            CaptureType::U128 => section.parse::<u128>().is_ok(),
+            CaptureType::Usize => section.parse::<usize>().is_ok(),
+            CaptureType::I8 => section.parse::<i8>().is_ok(),
+            CaptureType::I16 => section.parse::<i16>().is_ok(),
+            CaptureType::I32 => section.parse::<i32>().is_ok(),
+            CaptureType::I64 => section.parse::<i64>().is_ok(),
+            CaptureType::I128 => section.parse::<i128>().is_ok(),
+            CaptureType::Isize => section.parse::<isize>().is_ok(),
+            CaptureType::F32 => section.parse::<f32>().is_ok(),
+            CaptureType::F64 => section.parse::<f64>().is_ok(),
+            CaptureType::Bool => section.parse::<bool>().is_ok(),
+            CaptureType::Char => section.parse::<char>().is_ok(),
+        }
+    }
+}
+
+/// A character class, similar to those found in regular expressions, eg. `[a-z0-9_]+`.
+///
+/// Only a single bracketed class followed by an optional `+`, `*`, or `?` quantifier is supported.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CharClass {
+    /// If the class started with a `^`, characters must _not_ be within the ranges.
+    pub negated: bool,
+    /// Inclusive ranges of characters. Single characters are represented as a range of one.
+    pub ranges: Cow<'static, [(char, char)]>,
+    /// The minimum number of characters that must be present.
+    pub min: usize,
+    /// The maximum number of characters that may be present.
+    pub max: Option<usize>,
+}
+
+impl CharClass {
+    /// Checks if every character in the section is within the class,
+    /// and that the number of characters satisfies the quantifier.
+    pub fn is_satisfied_by(&self, section: &str) -> bool {
+        let count = section.chars().count();
+        count >= self.min
+            && self.max.map_or(true, |max| count <= max)
+            && section.chars().all(|c| self.contains(c))
+    }
+
+    fn contains(&self, c: char) -> bool {
+        let in_ranges = self
+            .ranges
+            .iter()
+            .any(|(start, end)| *start <= c && c <= *end);
+        in_ranges != self.negated
+    }
+}
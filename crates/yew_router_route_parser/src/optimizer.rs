@@ -1,33 +1,62 @@
 use crate::{
+    core::char_class,
     error::PrettyParseError,
-    parser::{parse, CaptureOrExact, RefCaptureVariant, RouteParserToken},
+    parser::{parse, CaptureOrExact, RefCaptureConstraint, RefCaptureVariant, RouteParserToken},
 };
 
-use crate::{core::FieldNamingScheme, CaptureVariant, MatcherToken};
+use crate::{
+    core::FieldNamingScheme, CaptureConstraint, CaptureVariant, MatcherToken, QueryParam,
+    QueryValue,
+};
+use std::borrow::Cow;
+
+impl<'a> From<RefCaptureConstraint<'a>> for CaptureConstraint {
+    fn from(c: RefCaptureConstraint<'a>) -> Self {
+        match c {
+            RefCaptureConstraint::Type(ty) => CaptureConstraint::Type(ty),
+            RefCaptureConstraint::Pattern(pattern) => CaptureConstraint::Pattern(
+                char_class(pattern)
+                    .expect("Pattern should have been validated by the parser.")
+                    .1,
+            ),
+        }
+    }
+}
 
 impl<'a> From<RefCaptureVariant<'a>> for CaptureVariant {
     fn from(v: RefCaptureVariant<'a>) -> Self {
         match v {
-            RefCaptureVariant::Named(s) => CaptureVariant::Named(s.to_string()),
-            RefCaptureVariant::ManyNamed(s) => CaptureVariant::ManyNamed(s.to_string()),
+            RefCaptureVariant::Named(s) => CaptureVariant::Named(s.to_string().into()),
+            RefCaptureVariant::ManyNamed(s) => CaptureVariant::ManyNamed(s.to_string().into()),
             RefCaptureVariant::NumberedNamed { sections, name } => CaptureVariant::NumberedNamed {
                 sections,
-                name: name.to_string(),
+                name: name.to_string().into(),
             },
             RefCaptureVariant::Unnamed => CaptureVariant::Unnamed,
             RefCaptureVariant::ManyUnnamed => CaptureVariant::ManyUnnamed,
             RefCaptureVariant::NumberedUnnamed { sections } => {
                 CaptureVariant::NumberedUnnamed { sections }
             }
+            RefCaptureVariant::NamedConstrained { name, constraint } => {
+                CaptureVariant::NamedConstrained {
+                    name: name.to_string().into(),
+                    constraint: constraint.into(),
+                }
+            }
         }
     }
 }
 
-impl<'a> From<CaptureOrExact<'a>> for MatcherToken {
+impl<'a> From<CaptureOrExact<'a>> for QueryValue {
     fn from(value: CaptureOrExact<'a>) -> Self {
         match value {
-            CaptureOrExact::Exact(m) => MatcherToken::Exact(m.to_string()),
-            CaptureOrExact::Capture(v) => MatcherToken::Capture(v.into()),
+            CaptureOrExact::Exact(m) => QueryValue::Exact(m.to_string().into()),
+            CaptureOrExact::Capture(v) => QueryValue::Capture(v.into()),
+            CaptureOrExact::OptionalCapture(v) => QueryValue::OptionalCapture(v.into()),
+            CaptureOrExact::DefaultedCapture { capture, default } => QueryValue::DefaultedCapture {
+                capture: capture.into(),
+                default: default.to_string().into(),
+            },
         }
     }
 }
@@ -37,13 +66,16 @@ impl<'a> RouteParserToken<'a> {
         match self {
             RouteParserToken::Separator => "/",
             RouteParserToken::Exact(literal) => &literal,
-            RouteParserToken::QueryBegin => "?",
-            RouteParserToken::QuerySeparator => "&",
             RouteParserToken::FragmentBegin => "#",
             RouteParserToken::Nothing
+            | RouteParserToken::QueryBegin
+            | RouteParserToken::QuerySeparator
             | RouteParserToken::Capture { .. }
             | RouteParserToken::Query { .. }
-            | RouteParserToken::End => unreachable!(),
+            | RouteParserToken::End
+            | RouteParserToken::Alternation(_)
+            | RouteParserToken::OptionalBegin
+            | RouteParserToken::OptionalEnd => unreachable!(),
         }
     }
 }
@@ -61,43 +93,50 @@ pub fn parse_str_and_optimize_tokens(
 ///
 /// In the process of converting the tokens, this function will condense multiple RouteParserTokens
 /// that represent literals into one Exact variant if multiple reducible tokens happen to occur in a row.
+///
+/// The parameters of the query section are collected into a single Query variant, so they can be
+/// matched regardless of their order.
+///
+/// Tokens within an optional section are converted into their own list, held by an Optional variant.
 pub fn convert_tokens(tokens: &[RouteParserToken]) -> Vec<MatcherToken> {
+    convert_tokens_impl(&mut tokens.iter())
+}
+
+/// Converts tokens until either the iterator is exhausted or the end of the current optional
+/// section is reached.
+fn convert_tokens_impl<'a, 'b: 'a>(
+    tokens: &mut impl Iterator<Item = &'a RouteParserToken<'b>>,
+) -> Vec<MatcherToken> {
     let mut new_tokens: Vec<MatcherToken> = vec![];
     let mut run: Vec<RouteParserToken> = vec![];
+    let mut query: Option<Vec<QueryParam>> = None;
 
     fn empty_run(run: &mut Vec<RouteParserToken>) -> Option<MatcherToken> {
         let segment = run.iter().map(RouteParserToken::as_str).collect::<String>();
         run.clear();
 
         if !segment.is_empty() {
-            Some(MatcherToken::Exact(segment))
+            Some(MatcherToken::Exact(segment.into()))
         } else {
             None
         }
     }
 
-    fn empty_run_with_query_cap_at_end(
-        run: &mut Vec<RouteParserToken>,
-        query_lhs: &str,
-    ) -> MatcherToken {
-        let segment = run
-            .iter()
-            .map(RouteParserToken::as_str)
-            .chain(Some(query_lhs))
-            .chain(Some("="))
-            .collect::<String>();
-        run.clear();
-
-        MatcherToken::Exact(segment)
-    }
-
-    for token in tokens.iter() {
+    while let Some(token) = tokens.next() {
         match token {
-            RouteParserToken::QueryBegin
-            | RouteParserToken::FragmentBegin
-            | RouteParserToken::Separator
-            | RouteParserToken::QuerySeparator
-            | RouteParserToken::Exact(_) => run.push(*token),
+            RouteParserToken::Separator | RouteParserToken::Exact(_) => run.push(*token),
+            RouteParserToken::QueryBegin | RouteParserToken::QuerySeparator => {
+                if let Some(current_run) = empty_run(&mut run) {
+                    new_tokens.push(current_run);
+                }
+                query.get_or_insert_with(Vec::new);
+            }
+            RouteParserToken::FragmentBegin => {
+                if let Some(params) = query.take() {
+                    new_tokens.push(MatcherToken::Query(params.into()));
+                }
+                run.push(*token)
+            }
             RouteParserToken::Capture(cap) => {
                 if let Some(current_run) = empty_run(&mut run) {
                     new_tokens.push(current_run);
@@ -107,23 +146,37 @@ pub fn convert_tokens(tokens: &[RouteParserToken]) -> Vec<MatcherToken> {
             RouteParserToken::Query {
                 ident,
                 capture_or_exact,
-            } => match capture_or_exact {
-                CaptureOrExact::Exact(s) => {
-                    run.push(RouteParserToken::Exact(ident));
-                    run.push(RouteParserToken::Exact("="));
-                    run.push(RouteParserToken::Exact(s));
-                }
-                CaptureOrExact::Capture(cap) => {
-                    new_tokens.push(empty_run_with_query_cap_at_end(&mut run, *ident));
-                    new_tokens.push(MatcherToken::Capture(CaptureVariant::from(*cap)))
-                }
-            },
+            } => query.get_or_insert_with(Vec::new).push(QueryParam {
+                key: ident.to_string().into(),
+                value: QueryValue::from(*capture_or_exact),
+            }),
             RouteParserToken::End => {
                 if let Some(current_run) = empty_run(&mut run) {
                     new_tokens.push(current_run);
                 }
+                if let Some(params) = query.take() {
+                    new_tokens.push(MatcherToken::Query(params.into()));
+                }
                 new_tokens.push(MatcherToken::End);
             }
+            RouteParserToken::Alternation(alternatives) => {
+                if let Some(current_run) = empty_run(&mut run) {
+                    new_tokens.push(current_run);
+                }
+                new_tokens.push(MatcherToken::Alternation(
+                    alternatives
+                        .split('|')
+                        .map(|alternative| Cow::Owned(alternative.to_string()))
+                        .collect(),
+                ))
+            }
+            RouteParserToken::OptionalBegin => {
+                if let Some(current_run) = empty_run(&mut run) {
+                    new_tokens.push(current_run);
+                }
+                new_tokens.push(MatcherToken::Optional(convert_tokens_impl(tokens).into()))
+            }
+            RouteParserToken::OptionalEnd => break,
             RouteParserToken::Nothing => {}
         }
     }
@@ -134,6 +187,9 @@ pub fn convert_tokens(tokens: &[RouteParserToken]) -> Vec<MatcherToken> {
             new_tokens.push(current_run);
         }
     }
+    if let Some(params) = query.take() {
+        new_tokens.push(MatcherToken::Query(params.into()));
+    }
 
     new_tokens
 }
@@ -148,4 +204,61 @@ mod tests {
         let tokens = parse_str_and_optimize_tokens("", FieldNamingScheme::Unit).unwrap();
         assert_eq!(tokens, vec![])
     }
+
+    #[test]
+    fn query_parameters_are_collected() {
+        let tokens = parse_str_and_optimize_tokens(
+            "/lorem?ipsum={ipsum}&dolor=sit#amet",
+            FieldNamingScheme::Named,
+        )
+        .unwrap();
+        let expected = vec![
+            MatcherToken::Exact("/lorem".into()),
+            MatcherToken::Query(Cow::Owned(vec![
+                QueryParam {
+                    key: "ipsum".into(),
+                    value: QueryValue::Capture(CaptureVariant::Named("ipsum".into())),
+                },
+                QueryParam {
+                    key: "dolor".into(),
+                    value: QueryValue::Exact("sit".into()),
+                },
+            ])),
+            MatcherToken::Exact("#amet".into()),
+        ];
+        assert_eq!(tokens, expected)
+    }
+
+    #[test]
+    fn alternation_splits_alternatives() {
+        let tokens =
+            parse_str_and_optimize_tokens("/(en|de)/about", FieldNamingScheme::Unit).unwrap();
+        let expected = vec![
+            MatcherToken::Exact("/".into()),
+            MatcherToken::Alternation(Cow::Owned(vec!["en".into(), "de".into()])),
+            MatcherToken::Exact("/about".into()),
+        ];
+        assert_eq!(tokens, expected)
+    }
+
+    #[test]
+    fn optional_section_creates_nested_tokens() {
+        let tokens = parse_str_and_optimize_tokens(
+            "/lorem[/ipsum[/{dolor}]]/sit",
+            FieldNamingScheme::Unnamed,
+        )
+        .unwrap();
+        let expected = vec![
+            MatcherToken::Exact("/lorem".into()),
+            MatcherToken::Optional(Cow::Owned(vec![
+                MatcherToken::Exact("/ipsum".into()),
+                MatcherToken::Optional(Cow::Owned(vec![
+                    MatcherToken::Exact("/".into()),
+                    MatcherToken::Capture(CaptureVariant::Named("dolor".into())),
+                ])),
+            ])),
+            MatcherToken::Exact("/sit".into()),
+        ];
+        assert_eq!(tokens, expected)
+    }
 }
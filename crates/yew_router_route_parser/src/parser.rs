@@ -1,11 +1,11 @@
 //! Parser that consumes a string and produces the first representation of the matcher.
 use crate::{
     core::{
-        capture, exact, fragment_exact, get_and, get_end, get_hash, get_question, get_slash,
-        nothing, query,
+        alternation, capture, exact, fragment_exact, get_and, get_close_optional, get_end,
+        get_hash, get_open_optional, get_question, get_slash, nothing, query,
     },
     error::{get_reason, ParseError, ParserErrorReason, PrettyParseError},
-    FieldNamingScheme,
+    CaptureType, FieldNamingScheme,
 };
 use nom::{branch::alt, IResult};
 // use crate::core::escaped_item;
@@ -20,6 +20,9 @@ pub enum RouteParserToken<'a> {
     Separator,
     /// Match a specific string.
     Exact(&'a str),
+    /// Match one of several specific strings - (x|y).
+    /// This holds the text between the parentheses, which is split into alternatives when optimized.
+    Alternation(&'a str),
     /// Match {_}. See `RefCaptureVariant` for more.
     Capture(RefCaptureVariant<'a>),
     /// Match ?
@@ -37,6 +40,10 @@ pub enum RouteParserToken<'a> {
     FragmentBegin,
     /// Match !
     End,
+    /// Match [ - the start of an optional section of the path.
+    OptionalBegin,
+    /// Match ] - the end of an optional section of the path.
+    OptionalEnd,
 }
 
 /// Token representing various types of captures.
@@ -68,6 +75,23 @@ pub enum RefCaptureVariant<'a> {
         /// The key to be entered in the `Matches` map.
         name: &'a str,
     },
+    /// {name:u32} or {name|[0-9]+} - captures a section with a given name, if it satisfies the
+    /// constraint.
+    NamedConstrained {
+        /// The key to be entered in the `Matches` map.
+        name: &'a str,
+        /// The constraint that the section must satisfy.
+        constraint: RefCaptureConstraint<'a>,
+    },
+}
+
+/// Borrowed form of a `CaptureConstraint`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefCaptureConstraint<'a> {
+    /// :u32
+    Type(CaptureType),
+    /// |[0-9]+ - The pattern has been validated, and holds the text of the character class.
+    Pattern(&'a str),
 }
 
 /// Either a Capture, or an Exact match
@@ -77,6 +101,15 @@ pub enum CaptureOrExact<'a> {
     Exact(&'a str),
     /// Match a capture variant.
     Capture(RefCaptureVariant<'a>),
+    /// {name}? - Match a capture variant if the key is present.
+    OptionalCapture(RefCaptureVariant<'a>),
+    /// {name=default} - Match a capture variant, using the default if the key is absent.
+    DefaultedCapture {
+        /// The capture variant.
+        capture: RefCaptureVariant<'a>,
+        /// The value captured if the key is absent.
+        default: &'a str,
+    },
 }
 
 /// Represents the states the parser can be in.
@@ -101,7 +134,10 @@ impl<'a> ParserState<'a> {
             ParserState::None => match token {
                 RouteParserToken::Separator
                 | RouteParserToken::Exact(_)
-                | RouteParserToken::Capture(_) => Ok(ParserState::Path { prev_token: token }),
+                | RouteParserToken::Alternation(_)
+                | RouteParserToken::Capture(_)
+                | RouteParserToken::OptionalBegin => Ok(ParserState::Path { prev_token: token }),
+                RouteParserToken::OptionalEnd => Err(ParserErrorReason::NotAllowedStateTransition),
                 RouteParserToken::QueryBegin => Ok(ParserState::FirstQuery { prev_token: token }),
                 RouteParserToken::QuerySeparator => Ok(ParserState::NthQuery { prev_token: token }),
                 RouteParserToken::Query { .. } => Err(ParserErrorReason::NotAllowedStateTransition),
@@ -111,7 +147,11 @@ impl<'a> ParserState<'a> {
             ParserState::Path { prev_token } => {
                 match prev_token {
                     RouteParserToken::Separator => match token {
-                        RouteParserToken::Exact(_) | RouteParserToken::Capture(_) => {
+                        RouteParserToken::Exact(_)
+                        | RouteParserToken::Alternation(_)
+                        | RouteParserToken::Capture(_)
+                        | RouteParserToken::OptionalBegin
+                        | RouteParserToken::OptionalEnd => {
                             Ok(ParserState::Path { prev_token: token })
                         }
                         RouteParserToken::QueryBegin => {
@@ -123,10 +163,13 @@ impl<'a> ParserState<'a> {
                         RouteParserToken::End => Ok(ParserState::End),
                         _ => Err(ParserErrorReason::NotAllowedStateTransition),
                     },
-                    RouteParserToken::Exact(_) => match token {
+                    RouteParserToken::Exact(_) | RouteParserToken::Alternation(_) => match token {
                         RouteParserToken::Exact(_)
+                        | RouteParserToken::Alternation(_)
                         | RouteParserToken::Separator
-                        | RouteParserToken::Capture(_) => {
+                        | RouteParserToken::Capture(_)
+                        | RouteParserToken::OptionalBegin
+                        | RouteParserToken::OptionalEnd => {
                             Ok(ParserState::Path { prev_token: token })
                         }
                         RouteParserToken::QueryBegin => {
@@ -139,7 +182,11 @@ impl<'a> ParserState<'a> {
                         _ => Err(ParserErrorReason::NotAllowedStateTransition),
                     },
                     RouteParserToken::Capture(_) => match token {
-                        RouteParserToken::Separator | RouteParserToken::Exact(_) => {
+                        RouteParserToken::Separator
+                        | RouteParserToken::Exact(_)
+                        | RouteParserToken::Alternation(_)
+                        | RouteParserToken::OptionalBegin
+                        | RouteParserToken::OptionalEnd => {
                             Ok(ParserState::Path { prev_token: token })
                         }
                         RouteParserToken::QueryBegin => {
@@ -151,6 +198,38 @@ impl<'a> ParserState<'a> {
                         RouteParserToken::End => Ok(ParserState::End),
                         _ => Err(ParserErrorReason::NotAllowedStateTransition),
                     },
+                    RouteParserToken::OptionalBegin => match token {
+                        RouteParserToken::Separator
+                        | RouteParserToken::Exact(_)
+                        | RouteParserToken::Alternation(_) => {
+                            Ok(ParserState::Path { prev_token: token })
+                        }
+                        RouteParserToken::Capture(_) => {
+                            Err(ParserErrorReason::CaptureNextToOptional)
+                        }
+                        RouteParserToken::OptionalEnd => Err(ParserErrorReason::EmptyOptional),
+                        _ => Err(ParserErrorReason::NotAllowedStateTransition),
+                    },
+                    RouteParserToken::OptionalEnd => match token {
+                        RouteParserToken::Separator
+                        | RouteParserToken::Exact(_)
+                        | RouteParserToken::Alternation(_)
+                        | RouteParserToken::OptionalBegin
+                        | RouteParserToken::OptionalEnd => {
+                            Ok(ParserState::Path { prev_token: token })
+                        }
+                        RouteParserToken::Capture(_) => {
+                            Err(ParserErrorReason::CaptureNextToOptional)
+                        }
+                        RouteParserToken::QueryBegin => {
+                            Ok(ParserState::FirstQuery { prev_token: token })
+                        }
+                        RouteParserToken::FragmentBegin => {
+                            Ok(ParserState::Fragment { prev_token: token })
+                        }
+                        RouteParserToken::End => Ok(ParserState::End),
+                        _ => Err(ParserErrorReason::NotAllowedStateTransition),
+                    },
                     _ => Err(ParserErrorReason::InvalidState), /* Other previous token types are
                                                                 * invalid within a Path state. */
                 }
@@ -213,6 +292,8 @@ impl<'a> ParserState<'a> {
 /// due to the fact that erroneous tokens can't be fed into the transition function.
 ///
 /// This continues until the string is exhausted, or none of the parsers for the current state can parse the current input.
+///
+/// Optional sections (`[...]`) may be nested, but must all be closed before the path ends.
 pub fn parse(
     mut i: &str,
     field_naming_scheme: FieldNamingScheme,
@@ -220,6 +301,7 @@ pub fn parse(
     let input = i;
     let mut tokens: Vec<RouteParserToken> = vec![];
     let mut state = ParserState::None;
+    let mut optional_depth: usize = 0;
 
     loop {
         let (ii, token) = parse_impl(i, &state, field_naming_scheme).map_err(|e| match e {
@@ -230,6 +312,18 @@ pub fn parse(
             },
             _ => panic!("parser should not be incomplete"),
         })?;
+        optional_depth = track_optional_depth(optional_depth, token).map_err(|reason| {
+            let error = ParseError {
+                reason: Some(reason),
+                expected: vec![],
+                offset: 0,
+            };
+            PrettyParseError {
+                error,
+                input,
+                remaining: i,
+            }
+        })?;
         i = ii;
         state = state.transition(token.clone()).map_err(|reason| {
             let error = ParseError {
@@ -250,9 +344,43 @@ pub fn parse(
             break;
         }
     }
+    if optional_depth > 0 {
+        let error = ParseError {
+            reason: Some(ParserErrorReason::UnclosedOptional),
+            expected: vec![],
+            offset: 0,
+        };
+        return Err(PrettyParseError {
+            error,
+            input,
+            remaining: i,
+        });
+    }
     Ok(tokens)
 }
 
+/// Keeps track of how many optional sections are open.
+///
+/// Optional sections only make sense within the path, so they must all be closed before
+/// the query, fragment or end token.
+fn track_optional_depth(depth: usize, token: RouteParserToken) -> Result<usize, ParserErrorReason> {
+    match token {
+        RouteParserToken::OptionalBegin => Ok(depth + 1),
+        RouteParserToken::OptionalEnd => depth
+            .checked_sub(1)
+            .ok_or(ParserErrorReason::UnmatchedOptionalEnd),
+        RouteParserToken::QueryBegin
+        | RouteParserToken::QuerySeparator
+        | RouteParserToken::FragmentBegin
+        | RouteParserToken::End
+            if depth > 0 =>
+        {
+            Err(ParserErrorReason::UnclosedOptional)
+        }
+        _ => Ok(depth),
+    }
+}
+
 fn parse_impl<'a>(
     i: &'a str,
     state: &ParserState,
@@ -264,8 +392,10 @@ fn parse_impl<'a>(
             get_question,
             get_and,
             get_hash,
+            get_open_optional,
             capture(field_naming_scheme),
             exact,
+            alternation,
             get_end,
             nothing,
         ))(i),
@@ -273,7 +403,10 @@ fn parse_impl<'a>(
             RouteParserToken::Separator => {
                 alt((
                     exact,
+                    alternation,
                     capture(field_naming_scheme),
+                    get_open_optional,
+                    get_close_optional,
                     get_question,
                     get_hash,
                     get_end,
@@ -289,11 +422,14 @@ fn parse_impl<'a>(
                     e
                 })
             }
-            RouteParserToken::Exact(_) => {
+            RouteParserToken::Exact(_) | RouteParserToken::Alternation(_) => {
                 alt((
                     get_slash,
                     exact, // This will handle escaped items
+                    alternation,
                     capture(field_naming_scheme),
+                    get_open_optional,
+                    get_close_optional,
                     get_question,
                     get_hash,
                     get_end,
@@ -309,18 +445,62 @@ fn parse_impl<'a>(
                 })
             }
             RouteParserToken::Capture(_) => {
-                alt((get_slash, exact, get_question, get_hash, get_end))(i).map_err(
-                    |mut e: nom::Err<ParseError>| {
-                        // Detect likely failures if the above failed to match.
-                        let reason: &mut Option<ParserErrorReason> = get_reason(&mut e);
-                        *reason = capture(field_naming_scheme)(i)
-                            .map(|_| ParserErrorReason::AdjacentCaptures)
-                            .or_else(|_| get_and(i).map(|_| ParserErrorReason::AndBeforeQuestion))
-                            .ok()
-                            .or(*reason);
-                        e
-                    },
-                )
+                alt((
+                    get_slash,
+                    exact,
+                    alternation,
+                    get_open_optional,
+                    get_close_optional,
+                    get_question,
+                    get_hash,
+                    get_end,
+                ))(i)
+                .map_err(|mut e: nom::Err<ParseError>| {
+                    // Detect likely failures if the above failed to match.
+                    let reason: &mut Option<ParserErrorReason> = get_reason(&mut e);
+                    *reason = capture(field_naming_scheme)(i)
+                        .map(|_| ParserErrorReason::AdjacentCaptures)
+                        .or_else(|_| get_and(i).map(|_| ParserErrorReason::AndBeforeQuestion))
+                        .ok()
+                        .or(*reason);
+                    e
+                })
+            }
+            RouteParserToken::OptionalBegin => {
+                alt((get_slash, exact, alternation))(i).map_err(|mut e: nom::Err<ParseError>| {
+                    // Detect likely failures if the above failed to match.
+                    let reason: &mut Option<ParserErrorReason> = get_reason(&mut e);
+                    *reason = capture(field_naming_scheme)(i)
+                        .map(|_| ParserErrorReason::CaptureNextToOptional)
+                        .or_else(|_| {
+                            get_close_optional(i).map(|_| ParserErrorReason::EmptyOptional)
+                        })
+                        .ok()
+                        .or(*reason);
+                    e
+                })
+            }
+            RouteParserToken::OptionalEnd => {
+                alt((
+                    get_slash,
+                    exact,
+                    alternation,
+                    get_open_optional,
+                    get_close_optional,
+                    get_question,
+                    get_hash,
+                    get_end,
+                ))(i)
+                .map_err(|mut e: nom::Err<ParseError>| {
+                    // Detect likely failures if the above failed to match.
+                    let reason: &mut Option<ParserErrorReason> = get_reason(&mut e);
+                    *reason = capture(field_naming_scheme)(i)
+                        .map(|_| ParserErrorReason::CaptureNextToOptional)
+                        .or_else(|_| get_and(i).map(|_| ParserErrorReason::AndBeforeQuestion))
+                        .ok()
+                        .or(*reason);
+                    e
+                })
             }
             _ => Err(nom::Err::Failure(ParseError {
                 reason: Some(ParserErrorReason::InvalidState),
@@ -542,6 +722,36 @@ mod test {
             ];
             assert_eq!(tokens, expected);
         }
+
+        #[test]
+        fn alternation_in_path() {
+            parse("/(en|de|fr)/about").expect("should parse");
+        }
+
+        #[test]
+        fn alternation_within_segment() {
+            parse("/lorem(ipsum|dolor){sit}").expect("should parse");
+        }
+
+        #[test]
+        fn square_brackets_in_query() {
+            parse("/lorem?ipsum[]={dolor}").expect("should parse");
+        }
+
+        #[test]
+        fn optional_section() {
+            parse("/lorem[/{ipsum}]").expect("should parse");
+        }
+
+        #[test]
+        fn nested_optional_sections() {
+            parse("/lorem[/{ipsum}[/dolor/{sit}]]").expect("should parse");
+        }
+
+        #[test]
+        fn optional_section_before_query() {
+            parse("/lorem[/ipsum]?dolor={sit}").expect("should parse");
+        }
     }
 
     mod does_not_parse {
@@ -572,6 +782,75 @@ mod test {
         }
 
 
+        #[test]
+        fn unknown_capture_type() {
+            let x = parse("/{lorem:string}").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::UnknownCaptureType));
+        }
+
+        #[test]
+        fn unterminated_char_class() {
+            let x = parse("/{lorem|[a-z}").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::BadCharClass));
+        }
+
+        #[test]
+        fn empty_alternative() {
+            let x = parse("/(en||fr)").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::BadAlternation));
+        }
+
+        #[test]
+        fn unterminated_alternation() {
+            let x = parse("/(en|fr/about").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::BadAlternation));
+        }
+
+        #[test]
+        fn unclosed_optional() {
+            let x = parse("/lorem[/{ipsum}").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::UnclosedOptional));
+        }
+
+        #[test]
+        fn optional_open_at_query() {
+            let x = parse("/lorem[/ipsum?dolor=sit]").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::UnclosedOptional));
+        }
+
+        #[test]
+        fn unmatched_optional_end() {
+            let x = parse("/lorem]").expect_err("Should not parse");
+            assert_eq!(
+                x.error.reason,
+                Some(ParserErrorReason::UnmatchedOptionalEnd)
+            );
+        }
+
+        #[test]
+        fn empty_optional() {
+            let x = parse("/lorem[]").expect_err("Should not parse");
+            assert_eq!(x.error.reason, Some(ParserErrorReason::EmptyOptional));
+        }
+
+        #[test]
+        fn capture_at_start_of_optional() {
+            let x = parse("/lorem/[{ipsum}]").expect_err("Should not parse");
+            assert_eq!(
+                x.error.reason,
+                Some(ParserErrorReason::CaptureNextToOptional)
+            );
+        }
+
+        #[test]
+        fn capture_after_optional() {
+            let x = parse("/lorem[/ipsum]{dolor}").expect_err("Should not parse");
+            assert_eq!(
+                x.error.reason,
+                Some(ParserErrorReason::CaptureNextToOptional)
+            );
+        }
+
         #[test]
         fn after_end() {
             let x = parse("/lorem/ipsum!/dolor").expect_err("Should not parse");
@@ -581,7 +860,10 @@ mod test {
 
     mod correct_parse {
         use super::*;
-        use crate::parser::{CaptureOrExact, RefCaptureVariant};
+        use crate::{
+            parser::{CaptureOrExact, RefCaptureConstraint, RefCaptureVariant},
+            CaptureType,
+        };
 
         #[test]
         fn starting_literal() {
@@ -628,6 +910,60 @@ mod test {
             assert_eq!(parsed, expected);
         }
 
+        #[test]
+        fn type_constrained_capture() {
+            let parsed = parse("/{lorem:u32}").unwrap();
+            let expected = vec![
+                RouteParserToken::Separator,
+                RouteParserToken::Capture(RefCaptureVariant::NamedConstrained {
+                    name: "lorem",
+                    constraint: RefCaptureConstraint::Type(CaptureType::U32),
+                }),
+            ];
+            assert_eq!(parsed, expected);
+        }
+
+        #[test]
+        fn pattern_constrained_capture() {
+            let parsed = parse("/{lorem|[0-9]+}/ipsum").unwrap();
+            let expected = vec![
+                RouteParserToken::Separator,
+                RouteParserToken::Capture(RefCaptureVariant::NamedConstrained {
+                    name: "lorem",
+                    constraint: RefCaptureConstraint::Pattern("[0-9]+"),
+                }),
+                RouteParserToken::Separator,
+                RouteParserToken::Exact("ipsum"),
+            ];
+            assert_eq!(parsed, expected);
+        }
+
+        #[test]
+        fn alternation() {
+            let parsed = parse("/(en|de)/about").unwrap();
+            let expected = vec![
+                RouteParserToken::Separator,
+                RouteParserToken::Alternation("en|de"),
+                RouteParserToken::Separator,
+                RouteParserToken::Exact("about"),
+            ];
+            assert_eq!(parsed, expected);
+        }
+
+        #[test]
+        fn optional_path() {
+            let parsed = parse("/lorem[/{ipsum}]").unwrap();
+            let expected = vec![
+                RouteParserToken::Separator,
+                RouteParserToken::Exact("lorem"),
+                RouteParserToken::OptionalBegin,
+                RouteParserToken::Separator,
+                RouteParserToken::Capture(RefCaptureVariant::Named("ipsum")),
+                RouteParserToken::OptionalEnd,
+            ];
+            assert_eq!(parsed, expected);
+        }
+
         #[test]
         fn query() {
             let parsed = parse("?query=this").unwrap();
@@ -641,6 +977,45 @@ mod test {
             assert_eq!(parsed, expected);
         }
 
+        #[test]
+        fn optional_query() {
+            let parsed = parse("?lorem={ipsum}?").unwrap();
+            let expected = vec![
+                RouteParserToken::QueryBegin,
+                RouteParserToken::Query {
+                    ident: "lorem",
+                    capture_or_exact: CaptureOrExact::OptionalCapture(RefCaptureVariant::Named(
+                        "ipsum",
+                    )),
+                },
+            ];
+            assert_eq!(parsed, expected);
+        }
+
+        #[test]
+        fn defaulted_query() {
+            let parsed = parse("?lorem={ipsum:u32=1}&dolor=sit").unwrap();
+            let expected = vec![
+                RouteParserToken::QueryBegin,
+                RouteParserToken::Query {
+                    ident: "lorem",
+                    capture_or_exact: CaptureOrExact::DefaultedCapture {
+                        capture: RefCaptureVariant::NamedConstrained {
+                            name: "ipsum",
+                            constraint: RefCaptureConstraint::Type(CaptureType::U32),
+                        },
+                        default: "1",
+                    },
+                },
+                RouteParserToken::QuerySeparator,
+                RouteParserToken::Query {
+                    ident: "dolor",
+                    capture_or_exact: CaptureOrExact::Exact("sit"),
+                },
+            ];
+            assert_eq!(parsed, expected);
+        }
+
         #[test]
         fn query_2_part() {
             let parsed = parse("?lorem=ipsum&dolor=sit").unwrap();
@@ -0,0 +1,303 @@
+//! Prefix tree used to dispatch a route to the matchers that could match it.
+use std::borrow::Cow;
+
+/// A radix tree over the literals and captures of a list of matchers.
+///
+/// Looking up a route finds every matcher whose literals the route contains, in the order and with
+/// the captures between them that the matcher describes, so that matchers that can't possibly
+/// match don't need to be tried.
+/// Matchers that share literals share the nodes for them, so a route is compared to each literal
+/// at most once, no matter how many matchers contain it.
+///
+/// Literals are compared without regard to ASCII case, and only the ASCII part of a literal is
+/// stored, so the candidates found for a route are always a superset of the matchers that will
+/// match it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PrefixTrie {
+    /// The nodes of the tree, the first of which is the root.
+    pub nodes: Cow<'static, [TrieNode]>,
+}
+
+/// A node within a `PrefixTrie`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TrieNode {
+    /// Edges to child nodes. No two edges of a node start with the same character.
+    pub edges: Cow<'static, [TrieEdge]>,
+    /// The index of the node that is reached after a capture, which might consume any characters.
+    pub capture: Option<usize>,
+    /// The indices of the matchers whose key ends at this node.
+    pub matchers: Cow<'static, [usize]>,
+}
+
+/// An edge between two nodes of a `PrefixTrie`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TrieEdge {
+    /// The lowercase literal that must be consumed to follow the edge.
+    pub label: Cow<'static, str>,
+    /// The index of the node that the edge leads to.
+    pub node: usize,
+}
+
+/// A part of the key that a matcher is stored under in a `PrefixTrie`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum KeyPart<'a> {
+    /// A literal that the route must contain.
+    Literal(&'a str),
+    /// A capture, which might consume any characters, including none.
+    Capture,
+}
+
+impl PrefixTrie {
+    /// Creates a tree from the keys of matchers.
+    ///
+    /// A key describes the start of the routes that its matcher can match, so it should end where
+    /// the matcher contains anything that isn't a literal or a capture.
+    /// The index of each key is used to identify its matcher.
+    pub fn new<'a, K: IntoIterator<Item = KeyPart<'a>>>(keys: impl IntoIterator<Item = K>) -> Self {
+        let mut trie = PrefixTrie {
+            nodes: Cow::Owned(vec![TrieNode::default()]),
+        };
+        for (index, key) in keys.into_iter().enumerate() {
+            let mut node = 0;
+            for part in key {
+                match part {
+                    KeyPart::Literal(literal) => {
+                        let (literal, truncated) = normalize(literal);
+                        node = trie.insert_literal(node, &literal);
+                        if truncated {
+                            break;
+                        }
+                    }
+                    KeyPart::Capture => node = trie.insert_capture(node),
+                }
+            }
+            trie.nodes.to_mut()[node].matchers.to_mut().push(index);
+        }
+        trie
+    }
+
+    /// Gets the node that is reached after a capture from the node, adding it if it's missing.
+    fn insert_capture(&mut self, node: usize) -> usize {
+        let nodes = self.nodes.to_mut();
+        match nodes[node].capture {
+            Some(child) => child,
+            None => {
+                let child = nodes.len();
+                nodes.push(TrieNode::default());
+                nodes[node].capture = Some(child);
+                child
+            }
+        }
+    }
+
+    /// Gets the node that is reached after the literal from the node, adding any missing nodes.
+    fn insert_literal(&mut self, mut node: usize, literal: &str) -> usize {
+        let nodes = self.nodes.to_mut();
+        let mut rest = literal;
+        loop {
+            let first = match rest.chars().next() {
+                Some(first) => first,
+                None => return node,
+            };
+            let edge_index = nodes[node]
+                .edges
+                .iter()
+                .position(|edge| edge.label.starts_with(first));
+            let edge_index = match edge_index {
+                Some(edge_index) => edge_index,
+                None => {
+                    let child = nodes.len();
+                    nodes.push(TrieNode::default());
+                    nodes[node].edges.to_mut().push(TrieEdge {
+                        label: Cow::Owned(rest.to_string()),
+                        node: child,
+                    });
+                    return child;
+                }
+            };
+
+            let edge = nodes[node].edges[edge_index].clone();
+            // Labels are ASCII, so any byte offset is a char boundary.
+            let common = edge
+                .label
+                .bytes()
+                .zip(rest.bytes())
+                .take_while(|(lhs, rhs)| lhs == rhs)
+                .count();
+            if common < edge.label.len() {
+                // Split the edge, so the common part leads to a new node.
+                let middle = nodes.len();
+                nodes.push(TrieNode {
+                    edges: Cow::Owned(vec![TrieEdge {
+                        label: Cow::Owned(edge.label[common..].to_string()),
+                        node: edge.node,
+                    }]),
+                    ..TrieNode::default()
+                });
+                nodes[node].edges.to_mut()[edge_index] = TrieEdge {
+                    label: Cow::Owned(edge.label[..common].to_string()),
+                    node: middle,
+                };
+                node = middle;
+            } else {
+                node = edge.node;
+            }
+            rest = &rest[common..];
+        }
+    }
+
+    /// Gets the indices of the matchers whose key the route starts with, in ascending order.
+    ///
+    /// The indices are written to the start of the buffer, which is returned truncated to them,
+    /// so that looking up a route doesn't allocate.
+    ///
+    /// # Panics
+    /// If the buffer is shorter than the number of candidates. A buffer with an element for every
+    /// matcher in the tree is always long enough.
+    pub fn candidates<'b>(&self, route: &str, buffer: &'b mut [usize]) -> &'b [usize] {
+        let mut len = 0;
+        self.visit(0, route, buffer, &mut len);
+        let candidates = &mut buffer[..len];
+        candidates.sort_unstable();
+        candidates
+    }
+
+    /// Adds the matchers of the node, and of every node that the rest of the route leads to from
+    /// it, to the candidates that haven't been found yet.
+    fn visit(&self, node: usize, rest: &str, buffer: &mut [usize], len: &mut usize) {
+        let node = &self.nodes[node];
+        for matcher in node.matchers.iter() {
+            if !buffer[..*len].contains(matcher) {
+                buffer[*len] = *matcher;
+                *len += 1;
+            }
+        }
+        // No two edges start with the same character, so at most one can be followed.
+        let next = node.edges.iter().find(|edge| {
+            rest.get(..edge.label.len())
+                .map_or(false, |start| start.eq_ignore_ascii_case(&edge.label))
+        });
+        if let Some(edge) = next {
+            self.visit(edge.node, &rest[edge.label.len()..], buffer, len);
+        }
+        if let Some(capture) = node.capture {
+            // The capture might end anywhere in the rest of the route.
+            let ends = rest.char_indices().map(|(index, _)| index);
+            for end in ends.chain(std::iter::once(rest.len())) {
+                self.visit(capture, &rest[end..], buffer, len);
+            }
+        }
+    }
+
+    /// Gets the number of matchers in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.iter().map(|node| node.matchers.len()).sum()
+    }
+
+    /// Returns true if the tree doesn't hold any matchers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for TrieNode {
+    fn default() -> Self {
+        TrieNode {
+            edges: Cow::Owned(vec![]),
+            capture: None,
+            matchers: Cow::Owned(vec![]),
+        }
+    }
+}
+
+/// Lowercases the literal, truncating it before the first non-ASCII character.
+///
+/// Returns true along with the literal if it was truncated.
+fn normalize(literal: &str) -> (String, bool) {
+    let normalized: String = literal
+        .chars()
+        .take_while(char::is_ascii)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    let truncated = normalized.len() < literal.len();
+    (normalized, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literals(literals: Vec<&str>) -> PrefixTrie {
+        PrefixTrie::new(
+            literals
+                .into_iter()
+                .map(|literal| vec![KeyPart::Literal(literal)]),
+        )
+    }
+
+    fn candidates(trie: &PrefixTrie, route: &str) -> Vec<usize> {
+        let mut buffer = vec![0; trie.len()];
+        trie.candidates(route, &mut buffer).to_vec()
+    }
+
+    #[test]
+    fn finds_all_matching_prefixes() {
+        let trie = literals(vec!["/api/users", "/api/posts", "/", "/api", "/about"]);
+        assert_eq!(candidates(&trie, "/api/posts/1"), vec![1, 2, 3]);
+        assert_eq!(candidates(&trie, "/api/users"), vec![0, 2, 3]);
+        assert_eq!(candidates(&trie, "/about"), vec![2, 4]);
+        assert_eq!(candidates(&trie, "/ap"), vec![2]);
+        assert_eq!(candidates(&trie, "lorem"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn empty_prefixes_are_always_candidates() {
+        let trie = literals(vec!["/lorem", ""]);
+        assert_eq!(candidates(&trie, "/ipsum"), vec![1]);
+        assert_eq!(candidates(&trie, "/lorem"), vec![0, 1]);
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let trie = literals(vec!["/Lorem", "/lorem/ipsum"]);
+        assert_eq!(candidates(&trie, "/LOREM/IPSUM"), vec![0, 1]);
+    }
+
+    #[test]
+    fn non_ascii_prefixes_are_truncated() {
+        let trie = literals(vec!["/café", "/cafe"]);
+        assert_eq!(candidates(&trie, "/CAFÉ"), vec![0]);
+        assert_eq!(candidates(&trie, "/cafe"), vec![0, 1]);
+    }
+
+    #[test]
+    fn literals_after_captures_must_be_present() {
+        use KeyPart::{Capture, Literal};
+        let trie = PrefixTrie::new(vec![
+            vec![Literal("/user/"), Capture, Literal("/posts")],
+            vec![Literal("/user/"), Capture, Literal("/settings")],
+            vec![Literal("/user/"), Capture],
+            vec![Capture, Literal("/about")],
+        ]);
+        assert_eq!(candidates(&trie, "/user/lorem/posts"), vec![0, 2]);
+        assert_eq!(candidates(&trie, "/USER/lorem/ipsum/settings"), vec![1, 2]);
+        assert_eq!(candidates(&trie, "/user/lorem"), vec![2]);
+        assert_eq!(candidates(&trie, "/lorem/about"), vec![3]);
+        assert_eq!(candidates(&trie, "/about"), vec![3]);
+        assert_eq!(candidates(&trie, "/lorem"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn matchers_are_found_once() {
+        use KeyPart::{Capture, Literal};
+        let trie = PrefixTrie::new(vec![vec![Capture, Literal("/"), Capture]]);
+        assert_eq!(candidates(&trie, "/lorem/ipsum/dolor"), vec![0]);
+    }
+
+    #[test]
+    fn keys_end_at_non_ascii_characters() {
+        use KeyPart::{Capture, Literal};
+        let trie = PrefixTrie::new(vec![vec![Literal("/café/"), Capture, Literal("/lorem")]]);
+        assert_eq!(candidates(&trie, "/cafe/ipsum"), vec![0]);
+    }
+}
@@ -0,0 +1,178 @@
+//! Warp filters that serve a yew-router app only for the routes that it handles.
+//!
+//! A single page app is typically served by returning its `index.html` for every path that isn't
+//! a file or an api, so that the app can be loaded from any of its routes.
+//! That also means that every path that the app doesn't handle gets a `200 OK`.
+//! The filters here match requests against the app's `Switch` type instead,
+//! so only the routes that the app handles are served, and every other request is rejected,
+//! which warp turns into a `404 Not Found`.
+//!
+//! Because the route enum can be shared between the app and the server, the two can't disagree.
+//!
+//! # Example
+//! ```no_run
+//! use warp::Filter;
+//! use yew_router::Switch;
+//!
+//! #[derive(Switch, Clone)]
+//! pub enum AppRoute {
+//!     #[to = "/users/{id}"]
+//!     User { id: u32 },
+//!     #[to = "/!"]
+//!     Home,
+//! }
+//!
+//! let index = yew_router_warp::index::<AppRoute>("static/index.html".into());
+//! let routes = warp::fs::dir("static").or(index);
+//! warp::serve(routes).run(([127, 0, 0, 1], 8000));
+//! ```
+#![deny(
+    missing_docs,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unstable_features,
+    unused_qualifications
+)]
+
+use std::path::PathBuf;
+use warp::{filters::BoxedFilter, http::Uri, path::FullPath, Filter, Reply};
+use yew_router::{route::Route, switch::trailing_slash_redirect, Switch};
+
+/// Extracts the route of the request, which is its path followed by its query, if it has one.
+pub fn route() -> BoxedFilter<(String,)> {
+    let query = warp::query::raw().or(warp::any().map(String::new)).unify();
+    warp::path::full()
+        .and(query)
+        .map(|path: FullPath, query: String| {
+            if query.is_empty() {
+                path.as_str().to_string()
+            } else {
+                format!("{}?{}", path.as_str(), query)
+            }
+        })
+        .boxed()
+}
+
+/// Extracts the item switched from the route of the request.
+///
+/// Requests with routes that `SW` doesn't match are rejected as not found.
+pub fn switch<SW>() -> BoxedFilter<(SW,)>
+where
+    SW: Switch + Send + 'static,
+{
+    route()
+        .and_then(|route: String| {
+            SW::switch(Route::new_no_state(route)).ok_or_else(warp::reject::not_found)
+        })
+        .boxed()
+}
+
+/// Serves the app's index file for `GET` requests with routes that `SW` matches.
+///
+/// Requests with routes that `SW` doesn't match are rejected as not found.
+///
+/// If `SW` redirects trailing slashes (`#[switch(trailing_slash = "redirect")]`),
+/// routes that only differ from the route built from their item by the `/` that ends their
+/// path are answered with a `301 Moved Permanently` to that route instead,
+/// like the `Router` does in the browser.
+pub fn index<SW>(index_file: PathBuf) -> BoxedFilter<(impl Reply,)>
+where
+    SW: Switch + Send + 'static,
+{
+    let redirect = route().and_then(|route: String| {
+        SW::switch(Route::new_no_state(&route))
+            .and_then(|switch| trailing_slash_redirect(&route, switch))
+            .and_then(|canonical| canonical.parse::<Uri>().ok())
+            .map(warp::redirect)
+            .ok_or_else(warp::reject::not_found)
+    });
+    let file = switch::<SW>()
+        .map(|_| ())
+        .untuple_one()
+        .and(warp::fs::file(index_file));
+
+    warp::get2().and(redirect.or(file)).boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::http::StatusCode;
+
+    const INDEX_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/index.html");
+
+    #[derive(Switch, Debug, Clone, PartialEq)]
+    pub enum AppRoute {
+        #[to = "/users/{id}?tab={tab}"]
+        UserTab { id: u32, tab: String },
+        #[to = "/users/{id}"]
+        User { id: u32 },
+        #[to = "/!"]
+        Home,
+    }
+
+    #[derive(Switch, Debug, Clone, PartialEq)]
+    #[switch(trailing_slash = "redirect")]
+    pub enum RedirectingRoute {
+        #[to = "/about!"]
+        About,
+    }
+
+    #[test]
+    fn route_includes_query() {
+        let route = warp::test::request()
+            .path("/users/5?tab=posts")
+            .filter(&route())
+            .unwrap();
+        assert_eq!(route, "/users/5?tab=posts");
+    }
+
+    #[test]
+    fn switch_extracts_item() {
+        let switched = warp::test::request()
+            .path("/users/5?tab=posts")
+            .filter(&switch::<AppRoute>())
+            .unwrap();
+        assert_eq!(
+            switched,
+            AppRoute::UserTab {
+                id: 5,
+                tab: "posts".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn index_served_for_matched_routes() {
+        let response = warp::test::request()
+            .path("/users/5")
+            .reply(&index::<AppRoute>(INDEX_FILE.into()));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(String::from_utf8_lossy(response.body()).contains("index"));
+    }
+
+    #[test]
+    fn unmatched_routes_not_found() {
+        let response = warp::test::request()
+            .path("/users/five")
+            .reply(&index::<AppRoute>(INDEX_FILE.into()));
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn trailing_slash_redirected() {
+        let response = warp::test::request()
+            .path("/about/")
+            .reply(&index::<RedirectingRoute>(INDEX_FILE.into()));
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(response.headers()["location"], "/about");
+
+        let response = warp::test::request()
+            .path("/about")
+            .reply(&index::<RedirectingRoute>(INDEX_FILE.into()));
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -32,6 +32,7 @@ macro_rules! define_router_state {
             #[doc = ">](route/struct.Route.html)."]
             pub type Route = $crate::route::Route<$StateT>;
 
+            #[cfg(feature = "service")]
             #[doc = "Alias to [RouteService<"]
             #[doc = $StateName]
             #[doc = ">](route_service/struct.RouteService.html)."]
@@ -2,6 +2,7 @@
 use crate::{
     agent::{RouteAgentDispatcher, RouteRequest},
     route::Route,
+    service::href,
     Switch,
 };
 use yew::prelude::*;
@@ -57,7 +58,7 @@ impl<SW: Switch + Clone + 'static, STATE: RouterState> Component for RouterAncho
         use stdweb::web::event::IEvent;
 
         let route: Route<STATE> = Route::from(self.props.route.clone());
-        let target: &str = route.as_str();
+        let target: String = href(route.as_str());
         #[cfg(feature = "std_web")]
         let cb = self.link.callback(|event: ClickEvent| {
             event.prevent_default();
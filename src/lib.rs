@@ -6,7 +6,9 @@
 //! that are used together to facilitate routing within the Yew framework.
 //! Among them are:
 //! * RouteService - Hooks into the History API and listens to `PopStateEvent`s to respond to users
-//!   clicking the back/forwards buttons.
+//!   clicking the back/forwards buttons. It can instead keep the route in the url's fragment
+//!   (`/#/route`) and listen to `hashchange` events, for hosts that can't serve every path.
+//!   The browser can be swapped for a `MemoryHistory`, to use routing outside of a browser.
 //! * RouteAgent - A singleton agent that owns a RouteService that provides an easy place for other
 //!   components and agents to hook into it.
 //! * Switch - A trait/derive macro that allows specification of how enums or structs can be constructed
@@ -40,6 +42,11 @@
 //!   "agent") will be included.
 //! * "agent" - If enabled, the RouteAgent and its associated types will be included.
 //! * "components" - If enabled, the accessory components will be made available.
+//!
+//! One of the "std_web" or "web_sys" features must be selected if "service" (or any feature that
+//! includes it) is enabled. With `default-features = false`, neither is needed,
+//! and `Switch`, its derive, `RouteMatcher`, and `Route` can be used on native targets,
+//! such as by a server that shares its route enum with a Yew app.
 
 #![deny(
     missing_docs,
@@ -116,6 +123,7 @@ pub mod prelude {
     pub use yew_router_macro::Switch;
 }
 
+#[cfg(feature = "unit_alias")]
 pub use alias::*;
 
 pub mod matcher;
@@ -1,32 +1,47 @@
 use crate::matcher::{
-    util::{consume_until, next_delimiter, tag_possibly_case_sensitive},
-    Captures, MatcherSettings,
+    util::{alternative, consume_until, next_delimiter, tag_possibly_case_sensitive},
+    Captures, MatchFailure, MatcherSettings, MultiCaptures, RefCaptures, TrailingSlash,
 };
+use crate::switch::decode_query_value;
 use log::trace;
 use nom::{
-    bytes::complete::{is_not, tag},
-    combinator::map,
+    bytes::complete::{is_not, tag, take_till},
     error::ErrorKind,
     sequence::terminated,
     IResult,
 };
-use std::{iter::Peekable, slice::Iter};
-use yew_router_route_parser::{CaptureVariant, MatcherToken};
+use percent_encoding::percent_decode_str;
+use std::borrow::Cow;
+use yew_router_route_parser::{
+    CaptureConstraint, CaptureVariant, MatcherToken, QueryParam, QueryValue,
+};
 
-/// Allows abstracting over capturing into a HashMap (Captures) or a Vec.
-trait CaptureCollection<'a> {
+/// Allows abstracting over capturing into a HashMap (Captures), a multi-map (MultiCaptures),
+/// a multi-map of borrowed values (RefCaptures), or a Vec.
+///
+/// Keys live as long as the matcher (`'k`), while values are slices of the route (`'v`).
+trait CaptureCollection<'k, 'v> {
     fn new2() -> Self;
-    fn insert2(&mut self, key: &'a str, value: String);
+    fn insert2(&mut self, key: &'k str, value: &'v str);
+    /// Inserts every value captured for a repeated key.
+    /// Collections that only hold one value per key keep the first.
+    fn insert_all2(&mut self, key: &'k str, values: &[&'v str]);
     fn extend2(&mut self, other: Self);
 }
 
-impl<'a> CaptureCollection<'a> for Captures<'a> {
+impl<'k, 'v> CaptureCollection<'k, 'v> for Captures<'k> {
     fn new2() -> Self {
         Captures::new()
     }
 
-    fn insert2(&mut self, key: &'a str, value: String) {
-        self.insert(key, value);
+    fn insert2(&mut self, key: &'k str, value: &'v str) {
+        self.insert(key, value.to_string());
+    }
+
+    fn insert_all2(&mut self, key: &'k str, values: &[&'v str]) {
+        if let Some(value) = values.first() {
+            self.insert2(key, value);
+        }
     }
 
     fn extend2(&mut self, other: Self) {
@@ -34,13 +49,17 @@ impl<'a> CaptureCollection<'a> for Captures<'a> {
     }
 }
 
-impl<'a> CaptureCollection<'a> for Vec<String> {
+impl<'k, 'v> CaptureCollection<'k, 'v> for Vec<String> {
     fn new2() -> Self {
         Vec::new()
     }
 
-    fn insert2(&mut self, _key: &'a str, value: String) {
-        self.push(value)
+    fn insert2(&mut self, _key: &'k str, value: &'v str) {
+        self.push(value.to_string())
+    }
+
+    fn insert_all2(&mut self, _key: &'k str, values: &[&'v str]) {
+        self.extend(values.first().map(|value| value.to_string()))
     }
 
     fn extend2(&mut self, other: Self) {
@@ -48,6 +67,48 @@ impl<'a> CaptureCollection<'a> for Vec<String> {
     }
 }
 
+impl<'k, 'v> CaptureCollection<'k, 'v> for MultiCaptures<'k> {
+    fn new2() -> Self {
+        MultiCaptures::new()
+    }
+
+    fn insert2(&mut self, key: &'k str, value: &'v str) {
+        self.entry(key).or_default().push(value.to_string());
+    }
+
+    fn insert_all2(&mut self, key: &'k str, values: &[&'v str]) {
+        self.entry(key)
+            .or_default()
+            .extend(values.iter().map(|value| value.to_string()));
+    }
+
+    fn extend2(&mut self, other: Self) {
+        for (key, values) in other {
+            self.entry(key).or_default().extend(values);
+        }
+    }
+}
+
+impl<'k: 'v, 'v> CaptureCollection<'k, 'v> for RefCaptures<'v> {
+    fn new2() -> Self {
+        RefCaptures::new()
+    }
+
+    fn insert2(&mut self, key: &'k str, value: &'v str) {
+        self.entry(key).or_default().push(value);
+    }
+
+    fn insert_all2(&mut self, key: &'k str, values: &[&'v str]) {
+        self.entry(key).or_default().extend_from_slice(values);
+    }
+
+    fn extend2(&mut self, other: Self) {
+        for (key, values) in other {
+            self.entry(key).or_default().extend(values);
+        }
+    }
+}
+
 #[allow(clippy::trivially_copy_pass_by_ref)]
 pub(super) fn match_into_map<'a, 'b: 'a>(
     tokens: &'b [MatcherToken],
@@ -56,6 +117,22 @@ pub(super) fn match_into_map<'a, 'b: 'a>(
     move |i: &str| matcher_impl(tokens, *settings, i)
 }
 
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub(super) fn match_into_multi_map<'a, 'b: 'a>(
+    tokens: &'b [MatcherToken],
+    settings: &'b MatcherSettings,
+) -> impl Fn(&'a str) -> IResult<&'a str, MultiCaptures<'b>> {
+    move |i: &str| matcher_impl(tokens, *settings, i)
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub(super) fn match_into_ref_map<'a, 'b: 'a>(
+    tokens: &'b [MatcherToken],
+    settings: &'b MatcherSettings,
+) -> impl Fn(&'a str) -> IResult<&'a str, RefCaptures<'a>> {
+    move |i: &'a str| matcher_impl(tokens, *settings, i)
+}
+
 #[allow(clippy::trivially_copy_pass_by_ref)]
 pub(super) fn match_into_vec<'a, 'b: 'a>(
     tokens: &'b [MatcherToken],
@@ -64,137 +141,490 @@ pub(super) fn match_into_vec<'a, 'b: 'a>(
     move |i: &str| matcher_impl(tokens, *settings, i)
 }
 
-fn matcher_impl<'a, 'b: 'a, CAP: CaptureCollection<'b>>(
+/// Walks over the tokens, descending into the contents of optional sections that are taken.
+///
+/// Once the contents of an optional section are exhausted, the tokens following the section are
+/// resumed.
+#[derive(Clone)]
+struct TokenCursor<'b> {
+    stack: Vec<&'b [MatcherToken]>,
+}
+
+impl<'b> TokenCursor<'b> {
+    fn new(tokens: &'b [MatcherToken]) -> Self {
+        TokenCursor {
+            stack: vec![tokens],
+        }
+    }
+
+    /// Makes the provided tokens the next ones to be visited.
+    fn push(&mut self, tokens: &'b [MatcherToken]) {
+        self.stack.push(tokens)
+    }
+
+    fn next(&mut self) -> Option<&'b MatcherToken> {
+        while let Some(top) = self.stack.last_mut() {
+            if let Some((first, rest)) = top.split_first() {
+                *top = rest;
+                return Some(first);
+            }
+            self.stack.pop();
+        }
+        None
+    }
+
+    fn peek(&self) -> Option<&'b MatcherToken> {
+        self.stack.iter().rev().find_map(|tokens| tokens.first())
+    }
+}
+
+fn matcher_impl<'a, 'b: 'a, CAP: CaptureCollection<'b, 'a>>(
     tokens: &'b [MatcherToken],
     settings: MatcherSettings,
-    mut i: &'a str,
+    i: &'a str,
 ) -> IResult<&'a str, CAP> {
     trace!("Attempting to match route: {:?} using: {:?}", i, tokens);
+    let (i, captures) = match_tokens(TokenCursor::new(tokens), settings, i, &mut Furthest::new())?;
+    trace!("Route Matched");
+
+    Ok((i, captures))
+}
+
+/// Matches the route, describing the furthest that the route got before a token failed to match
+/// if it doesn't match.
+pub(super) fn diagnose_impl<'a>(
+    tokens: &'a [MatcherToken],
+    settings: MatcherSettings,
+    i: &'a str,
+) -> Result<RefCaptures<'a>, MatchFailure> {
+    let mut furthest = Furthest::new();
+    match match_tokens(TokenCursor::new(tokens), settings, i, &mut furthest) {
+        Ok((_, captures)) => Ok(captures),
+        Err(_) => {
+            let (token, remaining) = furthest
+                .failure
+                .expect("A failed match should record the token that failed");
+            Err(MatchFailure {
+                matched: i.len() - remaining.len(),
+                token: token.clone(),
+                remaining: remaining.to_string(),
+            })
+        }
+    }
+}
 
-    let mut iter = tokens.iter().peekable();
+/// Keeps track of the token that failed furthest into the route.
+struct Furthest<'a, 'b> {
+    failure: Option<(&'b MatcherToken, &'a str)>,
+}
 
+impl<'a, 'b> Furthest<'a, 'b> {
+    fn new() -> Self {
+        Furthest { failure: None }
+    }
+
+    /// Records the failure if it happened at least as far into the route as the furthest so far.
+    ///
+    /// Later failures win ties, so a failure after a skipped optional section is preferred over
+    /// the failure within it.
+    fn record(&mut self, token: &'b MatcherToken, remaining: &'a str) {
+        match self.failure {
+            Some((_, furthest)) if furthest.len() < remaining.len() => {}
+            _ => self.failure = Some((token, remaining)),
+        }
+    }
+}
+
+/// Matches the remaining tokens of the cursor.
+///
+/// Optional sections are matched by first attempting to match the rest of the route with the
+/// section present, and falling back to matching it without the section.
+fn match_tokens<'a, 'b: 'a, CAP: CaptureCollection<'b, 'a>>(
+    mut cursor: TokenCursor<'b>,
+    settings: MatcherSettings,
+    mut i: &'a str,
+    furthest: &mut Furthest<'a, 'b>,
+) -> IResult<&'a str, CAP> {
     let mut captures: CAP = CAP::new2();
 
-    while let Some(token) = iter.next() {
-        i = match token {
+    while let Some(token) = cursor.next() {
+        let step = match token {
             MatcherToken::Exact(literal) => {
                 trace!("Matching '{}' against literal: '{}'", i, literal);
-                tag_possibly_case_sensitive(literal.as_str(), !settings.case_insensitive)(i)?.0
+                let matched =
+                    tag_possibly_case_sensitive(literal.as_ref(), !settings.case_insensitive)(i)
+                        .map(|(i, _)| i);
+                match literal.strip_suffix('/') {
+                    Some(without_slash)
+                        if matched.is_err()
+                            && settings.trailing_slash != TrailingSlash::Strict
+                            && ends_path(cursor.peek()) =>
+                    {
+                        // The route may omit the `/` that the path of the matcher ends with.
+                        tag_possibly_case_sensitive(without_slash, !settings.case_insensitive)(i)
+                            .ok()
+                            .map(|(i, _)| i)
+                            .filter(|i| is_path_end(i))
+                            .map_or(matched, Ok)
+                    }
+                    _ => matched,
+                }
+            }
+            MatcherToken::Alternation(alternatives) => {
+                trace!("Matching '{}' against alternatives: {:?}", i, alternatives);
+                alternative(alternatives, !settings.case_insensitive)(i).map(|(i, _)| i)
             }
-            MatcherToken::Capture(capture) => match &capture {
-                CaptureVariant::Named(name) => capture_named(i, &mut iter, &name, &mut captures)?,
-                CaptureVariant::ManyNamed(name) => {
-                    capture_many_named(i, &mut iter, &name, &mut captures)?
+            MatcherToken::Capture(capture) => {
+                if let Some(MatcherToken::Optional(optional)) = cursor.peek() {
+                    // The delimiter of the capture depends on if the optional section is present,
+                    // so both possibilities need to be tried.
+                    let mut without_optional = cursor.clone();
+                    without_optional.next();
+                    let mut with_optional = without_optional.clone();
+                    with_optional.push(optional);
+                    with_optional.push(std::slice::from_ref(token));
+                    without_optional.push(std::slice::from_ref(token));
+
+                    let (i, rest) = match_tokens::<CAP>(with_optional, settings, i, furthest)
+                        .or_else(|_| {
+                            match_tokens::<CAP>(without_optional, settings, i, furthest)
+                        })?;
+                    captures.extend2(rest);
+                    return Ok((i, captures));
                 }
-                CaptureVariant::NumberedNamed { sections, name } => {
-                    capture_numbered_named(i, &mut iter, Some((&name, &mut captures)), *sections)?
+                match &capture {
+                    CaptureVariant::Named(name) => {
+                        capture_named(i, &cursor, settings, &name, None, &mut captures)
+                    }
+                    CaptureVariant::NamedConstrained { name, constraint } => {
+                        capture_named(i, &cursor, settings, &name, Some(constraint), &mut captures)
+                    }
+                    CaptureVariant::ManyNamed(name) => {
+                        capture_many_named(i, &cursor, settings, &name, &mut captures)
+                    }
+                    CaptureVariant::NumberedNamed { sections, name } => capture_numbered_named(
+                        i,
+                        &cursor,
+                        settings,
+                        Some((&name, &mut captures)),
+                        *sections,
+                    ),
+                    CaptureVariant::Unnamed => {
+                        capture_named(i, &cursor, settings, "", None, &mut captures)
+                    }
+                    CaptureVariant::ManyUnnamed => {
+                        capture_many_named(i, &cursor, settings, "", &mut captures)
+                    }
+                    CaptureVariant::NumberedUnnamed { sections } => capture_numbered_named(
+                        i,
+                        &cursor,
+                        settings,
+                        Some(("", &mut captures)),
+                        *sections,
+                    ),
                 }
-                CaptureVariant::Unnamed => capture_named(i, &mut iter, "", &mut captures)?,
-                CaptureVariant::ManyUnnamed => capture_many_named(i, &mut iter, "", &mut captures)?,
-                CaptureVariant::NumberedUnnamed { sections } => {
-                    capture_numbered_named(i, &mut iter, Some(("", &mut captures)), *sections)?
+            }
+            MatcherToken::Query(params) => match_query(
+                skip_trailing_slash(i, settings),
+                params,
+                settings,
+                &mut captures,
+            ),
+            MatcherToken::Optional(optional) => {
+                let mut with_optional = cursor.clone();
+                with_optional.push(optional);
+                match match_tokens::<CAP>(with_optional, settings, i, furthest) {
+                    Ok((i, rest)) => {
+                        captures.extend2(rest);
+                        return Ok((i, captures));
+                    }
+                    Err(_) => {
+                        trace!("Optional section did not match, skipping it");
+                        Ok(i)
+                    }
                 }
-            },
+            }
             MatcherToken::End => {
-                if !i.is_empty() {
+                let rest = skip_trailing_slash(i, settings);
+                if !rest.is_empty() {
                     // this is approximately correct, but ultimately doesn't matter
-                    return Err(nom::Err::Failure((i, ErrorKind::Eof)));
+                    Err(nom::Err::Failure((i, ErrorKind::Eof)))
                 } else {
-                    i
+                    Ok(rest)
                 }
             }
         };
+        i = match step {
+            Ok(i) => i,
+            Err(e) => {
+                furthest.record(token, i);
+                return Err(e);
+            }
+        };
     }
-    trace!("Route Matched");
 
     Ok((i, captures))
 }
 
-fn capture_named<'a, 'b: 'a, CAP: CaptureCollection<'b>>(
+/// Checks if the token, which follows a literal, means that the literal ends the path.
+fn ends_path(next_token: Option<&MatcherToken>) -> bool {
+    match next_token {
+        None | Some(MatcherToken::End) | Some(MatcherToken::Query(_)) => true,
+        Some(_) => false,
+    }
+}
+
+/// Checks if the route is at the end of its path section.
+fn is_path_end(i: &str) -> bool {
+    i.is_empty() || i.starts_with(|c| c == '?' || c == '#')
+}
+
+/// Skips a `/` that ends the path, if the settings allow trailing slashes to be ignored.
+fn skip_trailing_slash(i: &str, settings: MatcherSettings) -> &str {
+    match i.strip_prefix('/') {
+        Some(rest) if settings.trailing_slash != TrailingSlash::Strict && is_path_end(rest) => rest,
+        _ => i,
+    }
+}
+
+/// Gets the token that follows a capture, which the capture should consume until.
+///
+/// A capture that is followed by the end of the route is treated like a capture at the end of the
+/// matcher, so that it only consumes the characters that it is allowed to.
+fn next_delimiting_token<'b>(cursor: &TokenCursor<'b>) -> Option<&'b MatcherToken> {
+    cursor.peek().filter(|token| **token != MatcherToken::End)
+}
+
+/// Produces a parser for the delimiter that ends a capture that is followed by the token.
+///
+/// If trailing slashes are ignored, a capture followed by a literal that ends the path with a `/`
+/// may also end at the end of the path, and a capture that ends the path may be followed by a `/`.
+fn capture_delimiter<'a, 'b: 'a>(
+    cursor: &TokenCursor<'b>,
+    next_token: &'b MatcherToken,
+    settings: MatcherSettings,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
+    let ignore_slash = settings.trailing_slash != TrailingSlash::Strict;
+    let slash_optional = ignore_slash
+        && match next_token {
+            MatcherToken::Exact(literal) if literal.ends_with('/') => {
+                let mut after_literal = cursor.clone();
+                after_literal.next();
+                ends_path(after_literal.peek())
+            }
+            _ => false,
+        };
+    let slash_allowed = ignore_slash && ends_path(Some(next_token));
+    let delimiter = next_delimiter(next_token);
+    move |i: &'a str| match delimiter(i) {
+        Err(_) if slash_optional && is_path_end(i) => Ok((i, "")),
+        Err(_) if slash_allowed && skip_trailing_slash(i, settings) != i => Ok((i, "")),
+        result => result,
+    }
+}
+
+/// Matches the whole query section, regardless of the order that the parameters appear in.
+///
+/// If a key appears more than once, every occurrence is captured, although collections that only
+/// hold one value per key will keep the first.
+/// Keys with empty values are treated as if they were absent.
+fn match_query<'a, 'b: 'a, CAP: CaptureCollection<'b, 'a>>(
     i: &'a str,
-    iter: &mut Peekable<Iter<MatcherToken>>,
+    params: &'b [QueryParam],
+    settings: MatcherSettings,
+    captures: &mut CAP,
+) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
+    log::trace!("Matching Query ({:?})", params);
+    let (ii, query) = match i.chars().next() {
+        Some('?') | Some('&') => take_till(|c| c == '#')(&i[1..])?,
+        // Without a query section, only optional and defaulted parameters can match.
+        _ => (i, ""),
+    };
+
+    let pairs = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.find('=') {
+            Some(index) => (&pair[..index], &pair[index + 1..]),
+            None => (pair, ""),
+        })
+        .collect::<Vec<_>>();
+    let equals = |lhs: &str, rhs: &str| {
+        if settings.case_insensitive {
+            lhs.eq_ignore_ascii_case(rhs)
+        } else {
+            lhs == rhs
+        }
+    };
+
+    for param in params {
+        let values = pairs
+            .iter()
+            .filter(|(key, value)| equals(key, &param.key) && !value.is_empty())
+            .map(|(_, value)| *value)
+            .collect::<Vec<_>>();
+        match (&param.value, values.is_empty()) {
+            (QueryValue::Exact(expected), false)
+                if values.iter().any(|value| equals(value, expected)) => {}
+            (QueryValue::Capture(capture), false)
+            | (QueryValue::OptionalCapture(capture), false)
+            | (QueryValue::DefaultedCapture { capture, .. }, false) => {
+                capture_query_values(i, capture, &values, captures)?
+            }
+            (QueryValue::OptionalCapture(_), true) => {}
+            (QueryValue::DefaultedCapture { capture, default }, true) => {
+                capture_query_values(i, capture, &[default.as_ref()], captures)?
+            }
+            _ => return Err(nom::Err::Error((i, ErrorKind::Tag))),
+        }
+    }
+
+    if !settings.ignore_unknown_query_params
+        && pairs
+            .iter()
+            .any(|(key, _)| !params.iter().any(|param| equals(key, &param.key)))
+    {
+        log::trace!("Query contained unknown parameters");
+        return Err(nom::Err::Error((i, ErrorKind::Verify)));
+    }
+    Ok(ii)
+}
+
+/// Captures the entire values of a query parameter, checking each against the constraint.
+fn capture_query_values<'a, 'b: 'a, CAP: CaptureCollection<'b, 'a>>(
+    i: &'a str,
+    capture: &'b CaptureVariant,
+    values: &[&'a str],
+    matches: &mut CAP,
+) -> Result<(), nom::Err<(&'a str, ErrorKind)>> {
+    let (capture_key, constraint) = match capture {
+        CaptureVariant::Named(name)
+        | CaptureVariant::ManyNamed(name)
+        | CaptureVariant::NumberedNamed { name, .. } => (name.as_ref(), None),
+        CaptureVariant::NamedConstrained { name, constraint } => (name.as_ref(), Some(constraint)),
+        CaptureVariant::Unnamed
+        | CaptureVariant::ManyUnnamed
+        | CaptureVariant::NumberedUnnamed { .. } => ("", None),
+    };
+    if let Some(constraint) = constraint {
+        if !values
+            .iter()
+            .all(|value| is_satisfied_when_decoded(constraint, value, true))
+        {
+            log::trace!("Capture ({}) did not satisfy constraint", capture_key);
+            return Err(nom::Err::Error((i, ErrorKind::Verify)));
+        }
+    }
+    matches.insert_all2(capture_key, values);
+    Ok(())
+}
+
+/// Checks the constraint against the value that the capture is decoded to before it is parsed,
+/// so that values which were encoded when a route was built satisfy the constraint again.
+///
+/// Query values have each `+` decoded to a space before they are percent-decoded.
+fn is_satisfied_when_decoded(constraint: &CaptureConstraint, captured: &str, query: bool) -> bool {
+    let captured = if query {
+        decode_query_value(captured)
+    } else {
+        Cow::Borrowed(captured)
+    };
+    percent_decode_str(&captured)
+        .decode_utf8()
+        .map(|decoded| constraint.is_satisfied_by(&decoded))
+        .unwrap_or(false)
+}
+
+fn capture_named<'a, 'b: 'a, CAP: CaptureCollection<'b, 'a>>(
+    i: &'a str,
+    cursor: &TokenCursor<'b>,
+    settings: MatcherSettings,
     capture_key: &'b str,
+    constraint: Option<&CaptureConstraint>,
     matches: &mut CAP,
 ) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
     log::trace!("Matching Named ({})", capture_key);
-    if let Some(_peaked_next_token) = iter.peek() {
-        let delimiter = next_delimiter(iter);
-        let (ii, captured) = consume_until(delimiter)(i)?;
-        matches.insert2(capture_key, captured);
-        Ok(ii)
+    let (ii, captured) = if let Some(peeked_next_token) = next_delimiting_token(cursor) {
+        let delimiter = capture_delimiter(cursor, peeked_next_token, settings);
+        consume_until(delimiter)(i)?
     } else {
-        let (ii, captured) = map(valid_capture_characters, String::from)(i)?;
-        matches.insert2(capture_key, captured);
-        Ok(ii)
+        valid_capture_characters(i)?
+    };
+    if captured.is_empty() && is_path_end(i) {
+        // The delimiter may be found at the end of the path if it ends with a `/` that the route
+        // omits, but that shouldn't let the capture match an empty section.
+        log::trace!("Capture ({}) would be empty", capture_key);
+        return Err(nom::Err::Error((i, ErrorKind::Verify)));
     }
+    if let Some(constraint) = constraint {
+        if !is_satisfied_when_decoded(constraint, captured, false) {
+            log::trace!("Capture ({}) did not satisfy constraint", capture_key);
+            return Err(nom::Err::Error((i, ErrorKind::Verify)));
+        }
+    }
+    matches.insert2(capture_key, captured);
+    Ok(ii)
 }
 
-fn capture_many_named<'a, 'b, CAP: CaptureCollection<'b>>(
+fn capture_many_named<'a, 'b: 'a, CAP: CaptureCollection<'b, 'a>>(
     i: &'a str,
-    iter: &mut Peekable<Iter<MatcherToken>>,
+    cursor: &TokenCursor<'b>,
+    settings: MatcherSettings,
     capture_key: &'b str,
     matches: &mut CAP,
 ) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
     log::trace!("Matching ManyUnnamed ({})", capture_key);
-    if let Some(_peaked_next_token) = iter.peek() {
-        let delimiter = next_delimiter(iter);
+    if let Some(peeked_next_token) = next_delimiting_token(cursor) {
+        let delimiter = capture_delimiter(cursor, peeked_next_token, settings);
         let (ii, captured) = consume_until(delimiter)(i)?;
         matches.insert2(&capture_key, captured);
         Ok(ii)
     } else if i.is_empty() {
         // If the route string is empty, return an empty value.
-        matches.insert2(&capture_key, "".to_string());
+        matches.insert2(&capture_key, "");
         Ok(i) // Match even if nothing is left
     } else {
-        let (ii, c) = map(valid_many_capture_characters, String::from)(i)?;
+        let (ii, c) = valid_many_capture_characters(i)?;
         matches.insert2(&capture_key, c);
         Ok(ii)
     }
 }
 
-fn capture_numbered_named<'a, 'b, CAP: CaptureCollection<'b>>(
+fn capture_numbered_named<'a, 'b: 'a, CAP: CaptureCollection<'b, 'a>>(
     mut i: &'a str,
-    iter: &mut Peekable<Iter<MatcherToken>>,
+    cursor: &TokenCursor<'b>,
+    settings: MatcherSettings,
     name_and_captures: Option<(&'b str, &mut CAP)>,
     mut sections: usize,
 ) -> Result<&'a str, nom::Err<(&'a str, ErrorKind)>> {
     log::trace!("Matching NumberedNamed ({})", sections);
-    let mut captured = "".to_string();
+    let start = i;
 
-    if let Some(_peaked_next_token) = iter.peek() {
+    if let Some(peeked_next_token) = next_delimiting_token(cursor) {
         while sections > 0 {
             if sections > 1 {
-                let (ii, c) = terminated(valid_capture_characters, tag("/"))(i)?;
-                i = ii;
-                captured += c;
-                captured += "/";
+                i = terminated(valid_capture_characters, tag("/"))(i)?.0;
             } else {
-                let delimiter = next_delimiter(iter);
-                let (ii, c) = consume_until(delimiter)(i)?;
-                i = ii;
-                captured += &c;
+                let delimiter = capture_delimiter(cursor, peeked_next_token, settings);
+                i = consume_until(delimiter)(i)?.0;
             }
             sections -= 1;
         }
     } else {
         while sections > 0 {
             if sections > 1 {
-                let (ii, c) = terminated(valid_capture_characters, tag("/"))(i)?;
-                i = ii;
-                captured += c;
-                captured += "/";
+                i = terminated(valid_capture_characters, tag("/"))(i)?.0;
             } else {
                 // Don't consume the next character on the last section
-                let (ii, c) = valid_capture_characters(i)?;
-                i = ii;
-                captured += c;
+                i = valid_capture_characters(i)?.0;
             }
             sections -= 1;
         }
     }
 
     if let Some((name, captures)) = name_and_captures {
-        captures.insert2(&name, captured);
+        // The sections are contiguous, so the capture is everything that was consumed.
+        captures.insert2(&name, &start[..start.len() - i.len()]);
     }
     Ok(i)
 }
@@ -216,7 +646,7 @@ mod integration_test {
 
     use yew_router_route_parser::{self, FieldNamingScheme};
 
-    use super::super::Captures;
+    use super::super::{Captures, MultiCaptures, TrailingSlash};
     //    use nom::combinator::all_consuming;
 
     #[test]
@@ -264,6 +694,174 @@ mod integration_test {
         assert_eq!(matches["ipsum"], "ipsum".to_string())
     }
 
+    #[test]
+    fn query_params_match_in_any_order() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/lorem?ipsum={ipsum}&dolor={dolor}",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (rest, matches) =
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem?dolor=2&ipsum=1")
+                .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["ipsum"], "1".to_string());
+        assert_eq!(matches["dolor"], "2".to_string());
+        matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem?dolor=2")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn optional_and_defaulted_query_params() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/lorem?ipsum={ipsum}?&dolor={dolor=1}",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (rest, matches) =
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem?dolor=2&ipsum=sit")
+                .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["ipsum"], "sit".to_string());
+        assert_eq!(matches["dolor"], "2".to_string());
+        let (rest, matches) = matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem")
+            .expect("should match");
+        assert_eq!(rest, "");
+        assert!(matches.get("ipsum").is_none());
+        assert_eq!(matches["dolor"], "1".to_string());
+    }
+
+    #[test]
+    fn repeated_query_params() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/lorem?tag={tag:u32}",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (rest, matches) = matcher_impl::<MultiCaptures>(
+            &x,
+            MatcherSettings::default(),
+            "/lorem?tag=1&tag=2&tag=3",
+        )
+        .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["tag"], vec!["1", "2", "3"]);
+        let (_, matches) =
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem?tag=1&tag=2")
+                .expect("should match");
+        assert_eq!(matches["tag"], "1".to_string());
+        matcher_impl::<MultiCaptures>(&x, MatcherSettings::default(), "/lorem?tag=1&tag=a")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn capture_followed_by_end() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/users/{id}!",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (rest, matches) = matcher_impl::<Captures>(&x, MatcherSettings::default(), "/users/1")
+            .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["id"], "1".to_string());
+        matcher_impl::<Captures>(&x, MatcherSettings::default(), "/users/1/settings")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn trailing_slash_policy() {
+        let ignore = MatcherSettings {
+            trailing_slash: TrailingSlash::Ignore,
+            ..MatcherSettings::default()
+        };
+        let cases = [
+            ("/about!", "/about/"),
+            ("/about/!", "/about"),
+            ("/about?q={q}", "/about/?q=1"),
+            ("/about/?q={q}", "/about?q=1"),
+            ("/users/{id}/!", "/users/1"),
+            ("/users/{id}!", "/users/1/"),
+        ];
+        for (matcher, route) in cases.iter() {
+            let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+                matcher,
+                FieldNamingScheme::Unnamed,
+            )
+            .expect("Should parse");
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), route)
+                .expect_err("should not match when strict");
+            let (rest, _) =
+                matcher_impl::<Captures>(&x, ignore, route).expect("should match when ignored");
+            assert_eq!(rest, "", "{} should consume {}", matcher, route);
+        }
+
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/users/{id}/!",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(&x, ignore, "/users/1").expect("should match");
+        assert_eq!(matches["id"], "1".to_string());
+        matcher_impl::<Captures>(&x, ignore, "/users/1/a").expect_err("should not match");
+    }
+
+    #[test]
+    fn trailing_slash_skips_optional_capture() {
+        let ignore = MatcherSettings {
+            trailing_slash: TrailingSlash::Ignore,
+            ..MatcherSettings::default()
+        };
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/opt[/{a}]/",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        for route in ["/opt", "/opt/"].iter() {
+            let (rest, matches) =
+                matcher_impl::<Captures>(&x, ignore, route).expect("should match");
+            assert_eq!(rest, "");
+            assert!(!matches.contains_key("a"), "{} shouldn't capture", route);
+        }
+        let (_, matches) = matcher_impl::<Captures>(&x, ignore, "/opt/1").expect("should match");
+        assert_eq!(matches["a"], "1".to_string());
+    }
+
+    #[test]
+    fn unknown_query_params() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "?lorem=ipsum",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (rest, _) =
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), "?dolor=sit&lorem=ipsum")
+                .expect("should match");
+        assert_eq!(rest, "");
+        let settings = MatcherSettings {
+            ignore_unknown_query_params: false,
+            ..Default::default()
+        };
+        matcher_impl::<Captures>(&x, settings, "?dolor=sit&lorem=ipsum")
+            .expect_err("should not match");
+        matcher_impl::<Captures>(&x, settings, "?lorem=ipsum").expect("should match");
+    }
+
+    #[test]
+    fn capture_before_query() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/{lorem}?ipsum={ipsum}#dolor",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (rest, matches) =
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), "/sit?ipsum=amet#dolor")
+                .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["lorem"], "sit".to_string());
+        assert_eq!(matches["ipsum"], "amet".to_string());
+    }
+
     #[test]
     fn match_n_paths_3() {
         let x = yew_router_route_parser::parse_str_and_optimize_tokens(
@@ -385,6 +983,119 @@ mod integration_test {
         matcher_impl::<Captures>(&x, settings, "/HeLLo").expect("should match");
     }
 
+    #[test]
+    fn type_constrained_capture() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/lorem/{ipsum:u32}",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem/42")
+            .expect("should match");
+        assert_eq!(matches["ipsum"], "42".to_string());
+        matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem/dolor")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn pattern_constrained_capture() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/{lorem|[a-z]+}/ipsum",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(&x, MatcherSettings::default(), "/dolor/ipsum")
+            .expect("should match");
+        assert_eq!(matches["lorem"], "dolor".to_string());
+        matcher_impl::<Captures>(&x, MatcherSettings::default(), "/dolor5/ipsum")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn alternation() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/(lorem|ipsum)/dolor",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem/dolor")
+            .expect("should match");
+        matcher_impl::<Captures>(&x, MatcherSettings::default(), "/ipsum/dolor")
+            .expect("should match");
+        matcher_impl::<Captures>(&x, MatcherSettings::default(), "/sit/dolor")
+            .expect_err("should not match");
+    }
+
+    #[test]
+    fn capture_before_alternation() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/{lorem}(.html|.htm)",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (rest, matches) =
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), "/ipsum.htm")
+                .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["lorem"], "ipsum".to_string());
+    }
+
+    #[test]
+    fn optional_section() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/lorem[/{ipsum}]",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (_, matches) = matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem/dolor")
+            .expect("should match");
+        assert_eq!(matches["ipsum"], "dolor".to_string());
+        let (rest, matches) = matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem")
+            .expect("should match");
+        assert_eq!(rest, "");
+        assert!(matches.get("ipsum").is_none());
+    }
+
+    #[test]
+    fn capture_before_optional_section() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/{lorem}[/ipsum]",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (rest, matches) =
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), "/dolor/ipsum")
+                .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["lorem"], "dolor".to_string());
+        let (rest, matches) = matcher_impl::<Captures>(&x, MatcherSettings::default(), "/dolor")
+            .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["lorem"], "dolor".to_string());
+    }
+
+    #[test]
+    fn nested_optional_sections() {
+        let x = yew_router_route_parser::parse_str_and_optimize_tokens(
+            "/lorem[/{ipsum}[/dolor]]/sit",
+            FieldNamingScheme::Unnamed,
+        )
+        .expect("Should parse");
+        let (rest, matches) =
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem/amet/dolor/sit")
+                .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["ipsum"], "amet".to_string());
+        let (rest, matches) =
+            matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem/amet/sit")
+                .expect("should match");
+        assert_eq!(rest, "");
+        assert_eq!(matches["ipsum"], "amet".to_string());
+        let (rest, _) = matcher_impl::<Captures>(&x, MatcherSettings::default(), "/lorem/sit")
+            .expect("should match");
+        assert_eq!(rest, "");
+    }
+
     #[test]
     fn end_token() {
         let x = yew_router_route_parser::parse_str_and_optimize_tokens(
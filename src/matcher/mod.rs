@@ -5,16 +5,60 @@ mod matcher_impl;
 mod util;
 
 use nom::IResult;
-use std::collections::HashSet;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+};
 use yew_router_route_parser::{parse_str_and_optimize_tokens, PrettyParseError};
 
-pub use yew_router_route_parser::{CaptureVariant, Captures, MatcherToken};
+pub use yew_router_route_parser::{
+    CaptureConstraint, CaptureType, CaptureVariant, Captures, CharClass, KeyPart, MatcherToken,
+    PrefixTrie, QueryParam, QueryValue, TrieEdge, TrieNode,
+};
+
+/// Alias of `HashMap<&'a str, Vec<String>>` that represents strings captured from a route,
+/// where a key may have been captured more than once.
+///
+/// Repeated query parameters, like `?tag=a&tag=b`, will have all of their values captured
+/// in the order that they appear.
+pub type MultiCaptures<'a> = HashMap<&'a str, Vec<String>>;
+
+/// Alias of `HashMap<&'a str, Vec<&'a str>>` that represents strings captured from a route,
+/// borrowed from the route itself.
+///
+/// Like `MultiCaptures`, every value of a repeated query parameter is kept.
+pub type RefCaptures<'a> = HashMap<&'a str, Vec<&'a str>>;
+
+/// Describes why a route didn't match a `RouteMatcher`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchFailure {
+    /// The number of bytes at the start of the route that were matched before the failure.
+    pub matched: usize,
+    /// The token that failed to match.
+    ///
+    /// If the matcher could have failed in more than one way, this is the token that failed
+    /// furthest into the route.
+    pub token: MatcherToken,
+    /// The part of the route that the token failed to match.
+    pub remaining: String,
+}
+
+impl fmt::Display for MatchFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} failed to match \"{}\", after matching {} bytes",
+            self.token, self.remaining, self.matched
+        )
+    }
+}
 
 /// Attempts to match routes, transform the route to Component props and render that Component.
 #[derive(Debug, PartialEq, Clone)]
 pub struct RouteMatcher {
     /// Tokens used to determine how the matcher will match a route string.
-    pub tokens: Vec<MatcherToken>,
+    pub tokens: Cow<'static, [MatcherToken]>,
     /// Settings
     pub settings: MatcherSettings,
 }
@@ -24,16 +68,38 @@ pub struct RouteMatcher {
 pub struct MatcherSettings {
     /// All literal matches do not care about case.
     pub case_insensitive: bool,
+    /// Query parameters with keys that aren't specified in the matcher won't prevent a match.
+    pub ignore_unknown_query_params: bool,
+    /// How a `/` at the end of the path section of a route is treated.
+    pub trailing_slash: TrailingSlash,
 }
 
 impl Default for MatcherSettings {
     fn default() -> Self {
         MatcherSettings {
             case_insensitive: false,
+            ignore_unknown_query_params: true,
+            trailing_slash: TrailingSlash::Strict,
         }
     }
 }
 
+/// Policy for a `/` at the end of the path section of a route.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TrailingSlash {
+    /// The route must have a trailing `/` if, and only if, the matcher ends its path with one.
+    ///
+    /// Because matchers don't need to consume the whole route, `/about` will still match
+    /// `/about/`, unless it ends with `!` or a query.
+    Strict,
+    /// Routes match regardless of whether the path ends with a `/`, so `/about` and `/about/`
+    /// match the same matchers.
+    Ignore,
+    /// Routes match as if the trailing `/` was ignored, but the `Router` replaces a route that
+    /// differs from the one built from its switch only by a trailing `/` with the built route.
+    Redirect,
+}
+
 impl RouteMatcher {
     /// Attempt to create a RouteMatcher from a "matcher string".
     pub fn try_from(i: &str) -> Result<Self, PrettyParseError> {
@@ -47,7 +113,8 @@ impl RouteMatcher {
             tokens: parse_str_and_optimize_tokens(
                 i,
                 yew_router_route_parser::FieldNamingScheme::Unnamed, // The most permissive scheme
-            )?, /* TODO this field type should be a superset of Named, but it would be better to source this from settings, and make sure that the macro generates settings as such. */
+            )?
+            .into(), /* TODO this field type should be a superset of Named, but it would be better to source this from settings, and make sure that the macro generates settings as such. */
             settings,
         })
     }
@@ -60,6 +127,33 @@ impl RouteMatcher {
         matcher_impl::match_into_map(&self.tokens, &self.settings)(i)
     }
 
+    /// Match a route string, collecting the results into a map that keeps every value captured
+    /// for a given key.
+    pub fn capture_route_into_multi_map<'a, 'b: 'a>(
+        &'b self,
+        i: &'a str,
+    ) -> IResult<&'a str, MultiCaptures<'a>> {
+        matcher_impl::match_into_multi_map(&self.tokens, &self.settings)(i)
+    }
+
+    /// Match a route string, collecting slices of the route into a map that keeps every value
+    /// captured for a given key.
+    ///
+    /// Unlike the other methods, nothing is copied out of the route.
+    pub fn capture_route_into_ref_map<'a, 'b: 'a>(
+        &'b self,
+        i: &'a str,
+    ) -> IResult<&'a str, RefCaptures<'a>> {
+        matcher_impl::match_into_ref_map(&self.tokens, &self.settings)(i)
+    }
+
+    /// Match a route string, describing why it didn't match if it doesn't.
+    ///
+    /// This is slower than the other methods, so it is intended for debugging.
+    pub fn diagnose<'a>(&'a self, i: &'a str) -> Result<RefCaptures<'a>, MatchFailure> {
+        matcher_impl::diagnose_impl(&self.tokens, self.settings, i)
+    }
+
     /// Match a route string, collecting the results into a vector.
     pub fn capture_route_into_vec<'a, 'b: 'a>(
         &'b self,
@@ -77,17 +171,38 @@ impl RouteMatcher {
                 .iter()
                 .fold(HashSet::new(), |mut acc: HashSet<&str>, token| {
                     match token {
-                        MatcherToken::Exact(_) | MatcherToken::End => {}
+                        MatcherToken::Exact(_)
+                        | MatcherToken::Alternation(_)
+                        | MatcherToken::End => {}
                         MatcherToken::Capture(capture) => match &capture {
                             CaptureVariant::ManyNamed(name)
                             | CaptureVariant::Named(name)
-                            | CaptureVariant::NumberedNamed { name, .. } => {
+                            | CaptureVariant::NumberedNamed { name, .. }
+                            | CaptureVariant::NamedConstrained { name, .. } => {
                                 acc.insert(&name);
                             }
                             CaptureVariant::Unnamed
                             | CaptureVariant::ManyUnnamed
                             | CaptureVariant::NumberedUnnamed { .. } => {}
                         },
+                        MatcherToken::Query(params) => {
+                            for param in params.iter() {
+                                if let Some(capture) = param.value.capture() {
+                                    match capture {
+                                        CaptureVariant::ManyNamed(name)
+                                        | CaptureVariant::Named(name)
+                                        | CaptureVariant::NumberedNamed { name, .. }
+                                        | CaptureVariant::NamedConstrained { name, .. } => {
+                                            acc.insert(&name);
+                                        }
+                                        CaptureVariant::Unnamed
+                                        | CaptureVariant::ManyUnnamed
+                                        | CaptureVariant::NumberedUnnamed { .. } => {}
+                                    }
+                                }
+                            }
+                        }
+                        MatcherToken::Optional(tokens) => acc.extend(capture_names_impl(tokens)),
                     }
                     acc
                 })
@@ -108,12 +223,76 @@ mod tests {
         fn from(tokens: Vec<RouteParserToken<'a>>) -> Self {
             let settings = MatcherSettings::default();
             RouteMatcher {
-                tokens: convert_tokens(&tokens),
+                tokens: convert_tokens(&tokens).into(),
                 settings,
             }
         }
     }
 
+    #[test]
+    fn static_matcher() {
+        static MATCHER: RouteMatcher = RouteMatcher {
+            tokens: Cow::Borrowed(&[
+                MatcherToken::Exact(Cow::Borrowed("/lorem/")),
+                MatcherToken::Capture(CaptureVariant::Named(Cow::Borrowed("ipsum"))),
+            ]),
+            settings: MatcherSettings {
+                case_insensitive: false,
+                ignore_unknown_query_params: true,
+                trailing_slash: TrailingSlash::Strict,
+            },
+        };
+        let (_, matches) = MATCHER
+            .capture_route_into_map("/lorem/dolor")
+            .expect("should parse");
+        assert_eq!(matches["ipsum"], "dolor".to_string())
+    }
+
+    #[test]
+    fn captures_borrow_from_route() {
+        let matcher = RouteMatcher::try_from("/{lorem}/{2:ipsum}?dolor={dolor}&sit={sit=amet}")
+            .expect("should parse");
+        let route = "/a/b/c?dolor=1&dolor=2".to_string();
+        let (_, matches) = matcher
+            .capture_route_into_ref_map(&route)
+            .expect("should match");
+        assert_eq!(matches["lorem"], vec!["a"]);
+        assert_eq!(matches["ipsum"], vec!["b/c"]);
+        assert_eq!(matches["dolor"], vec!["1", "2"]);
+        assert_eq!(matches["sit"], vec!["amet"]);
+        let route_range = route.as_ptr() as usize..route.as_ptr() as usize + route.len();
+        assert!(route_range.contains(&(matches["ipsum"][0].as_ptr() as usize)));
+    }
+
+    #[test]
+    fn diagnose_reports_furthest_failure() {
+        let matcher = RouteMatcher::try_from("/lorem[/dolor]/sit!").expect("should parse");
+        let failure = matcher
+            .diagnose("/lorem/consectetur")
+            .expect_err("should not match");
+        assert_eq!(failure.matched, "/lorem".len());
+        assert_eq!(failure.remaining, "/consectetur");
+        assert_eq!(failure.token, MatcherToken::Exact(Cow::Borrowed("/sit")));
+
+        let failure = matcher
+            .diagnose("/lorem/sit/")
+            .expect_err("should not match");
+        assert_eq!(failure.matched, "/lorem/sit".len());
+        assert_eq!(failure.token, MatcherToken::End);
+
+        let matcher = RouteMatcher::try_from("/lorem/{ipsum}/sit").expect("should parse");
+        let failure = matcher
+            .diagnose("/lorem/amet")
+            .expect_err("should not match");
+        assert_eq!(failure.matched, "/lorem/".len());
+        assert_eq!(
+            failure.token,
+            MatcherToken::Capture(CaptureVariant::Named(Cow::Borrowed("ipsum")))
+        );
+        let captures = matcher.diagnose("/lorem/amet/sit").expect("should match");
+        assert_eq!(captures["ipsum"], vec!["amet"]);
+    }
+
     #[test]
     fn basic_separator() {
         let tokens = vec![RouteParserToken::Separator];
@@ -1,13 +1,13 @@
 use nom::{
     bytes::complete::{tag, tag_no_case},
     character::complete::anychar,
-    combinator::{cond, map, peek, rest},
+    combinator::{cond, map, peek},
     error::{ErrorKind, ParseError},
     multi::many_till,
     sequence::pair,
     IResult,
 };
-use std::{iter::Peekable, rc::Rc, slice::Iter};
+use std::rc::Rc;
 use yew_router_route_parser::MatcherToken;
 
 /// Allows a configurable tag that can optionally be case insensitive.
@@ -24,12 +24,18 @@ pub fn tag_possibly_case_sensitive<'a, 'b: 'a>(
     )
 }
 
-/// Similar to alt, but works on a vector of tags.
-#[allow(unused)]
-pub fn alternative(alternatives: Vec<String>) -> impl Fn(&str) -> IResult<&str, &str> {
+/// Similar to alt, but works on a slice of tags.
+///
+/// The first alternative that matches is used.
+pub fn alternative<'a, 'b: 'a, S: AsRef<str>>(
+    alternatives: &'b [S],
+    is_sensitive: bool,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
     move |i: &str| {
-        for alternative in &alternatives {
-            if let done @ IResult::Ok(..) = tag(alternative.as_str())(i) {
+        for alternative in alternatives {
+            if let done @ IResult::Ok(..) =
+                tag_possibly_case_sensitive(alternative.as_ref(), is_sensitive)(i)
+            {
                 return done;
             }
         }
@@ -38,10 +44,10 @@ pub fn alternative(alternatives: Vec<String>) -> impl Fn(&str) -> IResult<&str,
 }
 
 /// Consumes the input until the provided parser succeeds.
-/// The consumed input is returned in the form of an allocated string.
+/// The consumed input is returned as a slice of the input.
 /// # Note
 /// `stop_parser` only peeks its input.
-pub fn consume_until<'a, F, E>(stop_parser: F) -> impl Fn(&'a str) -> IResult<&'a str, String, E>
+pub fn consume_until<'a, F, E>(stop_parser: F) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, E>
 where
     E: ParseError<&'a str>,
     F: Fn(&'a str) -> IResult<&'a str, &'a str, E>,
@@ -51,10 +57,9 @@ where
         anychar,
         peek(stop_parser), // once this succeeds, stop folding.
     ));
-    move |i: &str| {
-        let (i, (first, _stop)): (&str, (Vec<char>, &str)) = (f)(i)?;
-        let ret = first.into_iter().collect();
-        Ok((i, ret))
+    move |i: &'a str| {
+        let (rest, (_consumed, _stop)): (&str, (Vec<char>, &str)) = (f)(i)?;
+        Ok((rest, &i[..i.len() - rest.len()]))
     }
 }
 
@@ -64,21 +69,41 @@ where
 /// # Panics
 /// This function assumes that the next item after a Capture must be an Exact.
 /// If this is violated, this function will panic.
-pub fn next_delimiter<'a>(
-    iter: &mut Peekable<Iter<MatcherToken>>,
+pub fn next_delimiter<'a, 'b: 'a>(
+    next_token: &'b MatcherToken,
 ) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
-    let t: MatcherToken = iter
-        .peek()
-        .copied()
-        .cloned()
-        .expect("There must be at least one token to peak in next_delimiter");
-
-    move |i: &'a str| match &t {
-        MatcherToken::Exact(sequence) => tag(sequence.as_str())(i),
-        MatcherToken::End => rest(i),
+    move |i: &'a str| match next_token {
+        MatcherToken::Exact(sequence) => tag(sequence.as_ref())(i),
+        MatcherToken::Alternation(alternatives) => alternative(alternatives, true)(i),
+        MatcherToken::Query(_) => query_delimiter(i),
+        MatcherToken::End => end(i),
         MatcherToken::Capture(_) => {
             panic!("underlying parser should not allow two captures in a row")
         }
+        MatcherToken::Optional(_) => {
+            panic!("optional sections should be resolved before searching for a delimiter")
+        }
+    }
+}
+
+/// Succeeds without consuming anything at the end of the input.
+fn end(i: &str) -> IResult<&str, &str> {
+    if i.is_empty() {
+        Ok((i, ""))
+    } else {
+        Err(nom::Err::Error((i, ErrorKind::Eof)))
+    }
+}
+
+/// Succeeds without consuming anything at the start of a query section, a fragment, or the end
+/// of the input.
+///
+/// The query section may be absent if all of its parameters are optional.
+fn query_delimiter(i: &str) -> IResult<&str, &str> {
+    if i.is_empty() || i.starts_with(|c| c == '?' || c == '&' || c == '#') {
+        Ok((i, ""))
+    } else {
+        Err(nom::Err::Error((i, ErrorKind::Tag)))
     }
 }
 
@@ -90,7 +115,7 @@ mod tests {
     fn consume_until_simple() {
         let parser = consume_until::<_, ()>(tag("z"));
         let parsed = parser("abcz").expect("Should parse");
-        assert_eq!(parsed, ("z", "abc".to_string()))
+        assert_eq!(parsed, ("z", "abc"))
     }
 
     #[test]
@@ -102,26 +127,36 @@ mod tests {
 
     #[test]
     fn alternative_simple() {
-        let parser = alternative(
-            vec!["c", "d", "abc"]
-                .into_iter()
-                .map(String::from)
-                .collect(),
-        );
+        let alternatives: Vec<String> = vec!["c", "d", "abc"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let parser = alternative(&alternatives, true);
         let parsed = parser("abcz").expect("Should parse");
         assert_eq!(parsed, ("z", "abc"))
     }
 
     #[test]
     fn alternative_and_consume_until() {
-        let parser = consume_until(alternative(
-            vec!["c", "d", "abc"]
-                .into_iter()
-                .map(String::from)
-                .collect(),
-        ));
+        let alternatives: Vec<String> = vec!["c", "d", "abc"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let parser = consume_until(alternative(&alternatives, true));
         let parsed = parser("first_stuff_abc").expect("should parse");
-        assert_eq!(parsed, ("abc", "first_stuff_".to_string()))
+        assert_eq!(parsed, ("abc", "first_stuff_"))
+    }
+
+    #[test]
+    fn alternative_case_insensitive() {
+        let alternatives: Vec<String> = vec!["lorem", "ipsum"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let parser = alternative(&alternatives, false);
+        let parsed = parser("IPSUM/dolor").expect("Should parse");
+        assert_eq!(parsed, ("/dolor", "IPSUM"));
+        alternative(&alternatives, true)("IPSUM").expect_err("Should not parse");
     }
 
     #[test]
@@ -66,7 +66,7 @@ impl<STATE: Default> Route<STATE> {
 
 impl<STATE> fmt::Display for Route<STATE> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        std::fmt::Display::fmt(&self.route, f)
+        fmt::Display::fmt(&self.route, f)
     }
 }
 
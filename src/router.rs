@@ -3,6 +3,7 @@
 use crate::{
     agent::{RouteAgentBridge, RouteRequest},
     route::Route,
+    switch::trailing_slash_redirect,
     RouteState, Switch,
 };
 use std::{
@@ -61,7 +62,9 @@ impl<STATE> RouterState for STATE where STATE: RouteState + PartialEq {}
 pub struct Router<SW: Switch + Clone + 'static, STATE: RouterState = ()> {
     switch: Option<SW>,
     props: Props<STATE, SW>,
-    router_agent: RouteAgentBridge<STATE>,
+    /// The bridge to the agent, which isn't created if the route is given by the `route` prop.
+    router_agent: Option<RouteAgentBridge<STATE>>,
+    link: ComponentLink<Self>,
 }
 
 impl<SW, STATE> Router<SW, STATE>
@@ -99,6 +102,93 @@ where
     pub fn redirect<F: RedirectFn<SW, STATE> + 'static>(f: F) -> Option<Redirect<SW, STATE>> {
         Some(Redirect::new(f))
     }
+
+    /// Renders a route the same way that a `Router` with the props would, without creating one.
+    ///
+    /// This needs neither the `RouteAgent` nor a browser, so it can be used to render the `Html`
+    /// for a route on a server, or to pre-render the routes of an app.
+    /// Any `route` prop is ignored in favor of the route that is given.
+    pub fn view_route(props: &Props<STATE, SW>, route: Route<STATE>) -> Html {
+        let (switch, _) = switch_route(props, route);
+        view_switch(props, switch)
+    }
+
+    /// Switches the route given by the `route` prop, disconnecting from the agent,
+    /// because the given route is rendered instead of the one in the browser.
+    ///
+    /// Returns false if there is no `route` prop, so the router should follow the agent.
+    fn switch_route_prop(&mut self) -> bool {
+        match self.props.route.clone() {
+            Some(route) => {
+                self.router_agent = None;
+                self.switch = switch_route(&self.props, route).0;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Creates a bridge to the agent that updates the router whenever the route changes.
+fn connect_agent<SW, STATE>(link: &ComponentLink<Router<SW, STATE>>) -> RouteAgentBridge<STATE>
+where
+    STATE: RouterState,
+    SW: Switch + Clone + 'static,
+{
+    RouteAgentBridge::new(link.callback(Msg::UpdateRoute))
+}
+
+/// Switches the route, falling back to the redirect prop if the route doesn't match.
+///
+/// Also returns the route that should replace the route in the browser, if there is one.
+fn switch_route<SW, STATE>(
+    props: &Props<STATE, SW>,
+    route: Route<STATE>,
+) -> (Option<SW>, Option<Route<STATE>>)
+where
+    STATE: RouterState,
+    SW: Switch + Clone + 'static,
+{
+    let switch = SW::switch(route.clone());
+
+    #[cfg(debug_assertions)]
+    {
+        if switch.is_none() {
+            log::debug!("{}", SW::explain(&route.route));
+        }
+    }
+
+    if let Some(switch) = switch {
+        let replacement = trailing_slash_redirect(&route.route, switch.clone()).map(|canonical| {
+            log::trace!("Redirecting route to its canonical trailing slash.");
+            Route {
+                route: canonical,
+                state: route.state,
+            }
+        });
+        (Some(switch), replacement)
+    } else if let Some(redirect) = &props.redirect {
+        let redirected: SW = (&redirect.0)(route);
+        log::trace!("Route failed to match, but redirecting route to a known switch.");
+        (Some(redirected.clone()), Some(redirected.into()))
+    } else {
+        (None, None)
+    }
+}
+
+/// Renders the switched item with the render prop.
+fn view_switch<SW, STATE>(props: &Props<STATE, SW>, switch: Option<SW>) -> Html
+where
+    STATE: RouterState,
+    SW: Switch + Clone + 'static,
+{
+    match switch {
+        Some(switch) => (&props.render.0)(switch),
+        None => {
+            log::warn!("No route matched, provide a redirect prop to the router to handle cases where no route can be matched");
+            html! {"No route matched"}
+        }
+    }
 }
 
 /// Message for Router.
@@ -158,6 +248,15 @@ pub struct Props<STATE: RouterState, SW: Switch + Clone + 'static> {
     /// It is not strictly necessary as your Switch is capable of handling unknown routes using `#[to="/{*:any}"]`.
     #[prop_or_default]
     pub redirect: Option<Redirect<SW, STATE>>,
+    /// The route to render, instead of the one in the browser.
+    ///
+    /// While this is set, the router isn't connected to the `RouteAgent`,
+    /// so it neither follows nor changes the route in the browser.
+    /// If it is unset later, the router connects to the agent and renders the browser's route.
+    /// This allows the router to be rendered for a given route, such as during server-side
+    /// rendering, where there is no browser.
+    #[prop_or_default]
+    pub route: Option<Route<STATE>>,
 }
 
 impl<STATE: RouterState, SW: Switch + Clone> Debug for Props<STATE, SW> {
@@ -175,43 +274,37 @@ where
     type Properties = Props<STATE, SW>;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        let callback = link.callback(Msg::UpdateRoute);
-        let router_agent = RouteAgentBridge::new(callback);
-
-        Router {
+        let mut router = Router {
             switch: Default::default(), /* This must be updated by immediately requesting a route
-                                         * update from the service bridge. */
+                                         * update from the service bridge, unless the route is
+                                         * given by the `route` prop. */
             props,
-            router_agent,
+            router_agent: None,
+            link,
+        };
+        if !router.switch_route_prop() {
+            router.router_agent = Some(connect_agent(&router.link));
         }
+        router
     }
 
     fn mounted(&mut self) -> ShouldRender {
-        self.router_agent.send(RouteRequest::GetCurrentRoute);
+        if let Some(router_agent) = &mut self.router_agent {
+            router_agent.send(RouteRequest::GetCurrentRoute);
+        }
         false
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::UpdateRoute(route) => {
-                let mut switch = SW::switch(route.clone());
-
-                if switch.is_none() {
-                    if let Some(redirect) = &self.props.redirect {
-                        let redirected: SW = (&redirect.0)(route);
-
-                        log::trace!(
-                            "Route failed to match, but redirecting route to a known switch."
-                        );
-                        // Replace the route in the browser with the redirected.
-                        self.router_agent
-                            .send(RouteRequest::ReplaceRouteNoBroadcast(
-                                redirected.clone().into(),
-                            ));
-                        switch = Some(redirected)
-                    }
+                let (switch, replacement) = switch_route(&self.props, route);
+                if let (Some(router_agent), Some(replacement)) =
+                    (&mut self.router_agent, replacement)
+                {
+                    // Replace the route in the browser with the redirected.
+                    router_agent.send(RouteRequest::ReplaceRouteNoBroadcast(replacement));
                 }
-
                 self.switch = switch;
                 true
             }
@@ -220,16 +313,99 @@ where
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
         self.props = props;
+        if !self.switch_route_prop() && self.router_agent.is_none() {
+            let mut router_agent = connect_agent(&self.link);
+            router_agent.send(RouteRequest::GetCurrentRoute);
+            self.router_agent = Some(router_agent);
+        }
         true
     }
 
     fn view(&self) -> VNode {
-        match self.switch.clone() {
-            Some(switch) => (&self.props.render.0)(switch),
-            None => {
-                log::warn!("No route matched, provide a redirect prop to the router to handle cases where no route can be matched");
-                html! {"No route matched"}
-            }
+        view_switch(&self.props, self.switch.clone())
+    }
+}
+
+// Rendering `Html` natively only links with `web_sys`.
+#[cfg(all(test, feature = "web_sys"))]
+mod test {
+    use super::*;
+
+    /// Matches `/pages/{number}`.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Page(u32);
+
+    impl Switch for Page {
+        fn from_route_part<STATE>(
+            part: String,
+            state: Option<STATE>,
+        ) -> (Option<Self>, Option<STATE>) {
+            let page = part
+                .strip_prefix("/pages/")
+                .and_then(|number| number.parse().ok())
+                .map(Page);
+            (page, state)
         }
+
+        fn build_route_section<STATE>(self, route: &mut String) -> Option<STATE> {
+            route.push_str(&format!("/pages/{}", self.0));
+            None
+        }
+    }
+
+    fn props(route: Option<&str>) -> Props<(), Page> {
+        Props {
+            render: Router::render(|page: Page| html! {format!("Page {}", page.0)}),
+            redirect: None,
+            route: route.map(Route::new_no_state),
+        }
+    }
+
+    #[test]
+    fn view_route_renders_switched_route() {
+        let html = Router::view_route(&props(None), Route::new_no_state("/pages/1"));
+        assert_eq!(html, html! {"Page 1"});
+        let html = Router::view_route(&props(None), Route::new_no_state("/missing"));
+        assert_eq!(html, html! {"No route matched"});
+    }
+
+    #[test]
+    fn view_route_redirects_unmatched_route() {
+        let mut props = props(None);
+        props.redirect = Router::redirect(|_: Route| Page(404));
+        let html = Router::view_route(&props, Route::new_no_state("/missing"));
+        assert_eq!(html, html! {"Page 404"});
+    }
+
+    #[test]
+    fn view_route_ignores_route_prop() {
+        let html = Router::view_route(&props(Some("/pages/1")), Route::new_no_state("/pages/2"));
+        assert_eq!(html, html! {"Page 2"});
+    }
+
+    // `Router::create` and `Router::change` can connect to the agent, which needs a browser,
+    // so the router is built here as it would be for a `route` prop.
+    #[test]
+    fn route_prop_switches_router() {
+        let mut router = Router {
+            switch: None,
+            props: props(Some("/pages/1")),
+            router_agent: None,
+            link: ComponentLink::new(),
+        };
+        assert!(router.switch_route_prop());
+        assert_eq!(router.view(), html! {"Page 1"});
+
+        router.props = props(Some("/pages/2"));
+        assert!(router.switch_route_prop());
+        assert_eq!(router.switch, Some(Page(2)));
+        assert_eq!(router.view(), html! {"Page 2"});
+
+        router.props = props(Some("/missing"));
+        assert!(router.switch_route_prop());
+        assert_eq!(router.view(), html! {"No route matched"});
+
+        router.props = props(None);
+        assert!(!router.switch_route_prop());
     }
 }
@@ -3,38 +3,141 @@
 use yew::callback::Callback;
 
 use crate::route::{Route, RouteState};
-use cfg_if::cfg_if;
-use cfg_match::cfg_match;
-use std::marker::PhantomData;
+use std::{
+    any::{type_name, Any},
+    cell::{Cell, RefCell},
+    fmt::Debug,
+};
 
-cfg_if! {
-    if #[cfg(feature = "std_web")] {
-        use stdweb::{
-            js,
-            unstable::{TryFrom, TryInto},
-            web::{event::PopStateEvent, window, EventListenerHandle, History, IEventTarget, Location},
-            Value,
-        };
-    } else if #[cfg(feature = "web_sys")] {
-        use web_sys::{History, Location, PopStateEvent};
-        use gloo::events::EventListener;
-        use wasm_bindgen::{JsValue as Value, JsCast};
+mod browser;
+pub use self::browser::BrowserHistory;
+mod memory;
+pub use self::memory::MemoryHistory;
+
+/// Where in the browser's url the route is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoutingMode {
+    /// The route is the path, query, and fragment of the url, like `/users/5?tab=posts`.
+    ///
+    /// Changes made by the user pressing 'forward' or 'back' are detected with `popstate` events.
+    History,
+    /// The route is stored in the fragment of the url, like `/#/users/5?tab=posts`,
+    /// and the rest of the url is left alone.
+    ///
+    /// This allows an app to be served from hosts that can't serve the same page for every path,
+    /// such as static file hosts or `file://` urls.
+    /// Changes to the route are detected with `hashchange` events.
+    Hash,
+}
+
+impl Default for RoutingMode {
+    fn default() -> Self {
+        RoutingMode::History
     }
 }
 
+thread_local! {
+    static DEFAULT_MODE: Cell<RoutingMode> = Cell::new(RoutingMode::default());
+    static DEFAULT_BASE_PATH: RefCell<String> = RefCell::new(String::new());
+    /// The name of the backend's `STATE` type, and its `MakeHistory<STATE>`.
+    static DEFAULT_HISTORY: RefCell<Option<(&'static str, Box<dyn Any>)>> = RefCell::new(None);
+}
+
+/// Creates the backend for a route service created with `RouteService::new`.
+type MakeHistory<STATE> = Box<dyn Fn() -> Box<dyn HistoryBackend<STATE>>>;
+
+/// Sets the mode used by route services created with `RouteService::new`.
+///
+/// Because the `RouteAgent` creates its own service, this is how the `Router` and the components
+/// that change the route are made to use hash-based routing.
+/// It should be called before any of them are created, typically at the start of `main`.
+pub fn set_default_routing_mode(mode: RoutingMode) {
+    DEFAULT_MODE.with(|default_mode| default_mode.set(mode))
+}
+
+/// Gets the mode used by route services created with `RouteService::new`.
+pub fn default_routing_mode() -> RoutingMode {
+    DEFAULT_MODE.with(Cell::get)
+}
+
+/// Sets the base path used by route services created with `RouteService::new`.
+///
+/// Like `set_default_routing_mode`, this configures the service created by the `RouteAgent`,
+/// and should be called before any routing components are created.
+/// See `RouteService::with_base_path` for how the base path is used.
+pub fn set_default_base_path(base_path: &str) {
+    DEFAULT_BASE_PATH
+        .with(|default_base_path| *default_base_path.borrow_mut() = normalize_base_path(base_path))
+}
+
+/// Gets the base path used by route services created with `RouteService::new`.
+pub fn default_base_path() -> String {
+    DEFAULT_BASE_PATH.with(|default_base_path| default_base_path.borrow().clone())
+}
+
+/// Sets the history backend used by route services created with `RouteService::new`.
+///
+/// Each service is given a clone of the backend.
+/// This is how the `RouteAgent`, and the `Router` and components that use it,
+/// are made to use a `MemoryHistory` in tests, instead of the browser's history.
+///
+/// # Panics
+/// Once this is set, `RouteService::new` panics if it is called for a `STATE` type other than the
+/// backend's, instead of quietly falling back to the browser's history.
+pub fn set_default_history<STATE, H>(history: H)
+where
+    STATE: 'static,
+    H: HistoryBackend<STATE> + Clone + 'static,
+{
+    let make_history: MakeHistory<STATE> = Box::new(move || Box::new(history.clone()));
+    DEFAULT_HISTORY.with(|default_history| {
+        *default_history.borrow_mut() = Some((type_name::<STATE>(), Box::new(make_history)))
+    })
+}
+
+/// Gets the url that navigates to the route, for use as the `href` of a link.
+pub fn href(route: &str) -> String {
+    url_for_route(
+        &route_under_base_path(route, &default_base_path()),
+        default_routing_mode(),
+    )
+}
+
+/// Stores the history of routes that a `RouteService` navigates through.
+///
+/// `BrowserHistory`, which uses the browser's History API, is used unless another backend is given
+/// to `RouteService::with_history` or `set_default_history`.
+/// `MemoryHistory` keeps its entries in memory, so it can be used in native tests.
+pub trait HistoryBackend<STATE>: Debug {
+    /// Creates a new entry for the route after the current one, and makes it current.
+    ///
+    /// Any entries after the current one are discarded.
+    fn push(&mut self, route: &str, state: STATE);
+
+    /// Replaces the current entry with one for the route.
+    fn replace(&mut self, route: &str, state: STATE);
+
+    /// Gets the route of the current entry.
+    fn current(&self) -> Route<STATE>;
+
+    /// Registers a callback that is called with the new route when the current entry changes
+    /// for a reason other than `push` or `replace`, such as the user pressing 'back'.
+    ///
+    /// Each backend has at most one callback: it replaces any callback registered before through
+    /// the same backend, and is no longer called once the backend is dropped.
+    fn listen(&mut self, callback: Callback<Route<STATE>>);
+}
+
 /// A service that facilitates manipulation of the browser's URL bar and responding to browser events
 /// when users press 'forward' or 'back'.
 ///
+/// The service delegates to a `HistoryBackend`, which is a `BrowserHistory` by default.
+///
 /// The `T` determines what route state can be stored in the route service.
 #[derive(Debug)]
 pub struct RouteService<STATE = ()> {
-    history: History,
-    location: Location,
-    #[cfg(feature = "std_web")]
-    event_listener: Option<EventListenerHandle>,
-    #[cfg(feature = "web_sys")]
-    event_listener: Option<EventListener>,
-    phantom_data: PhantomData<STATE>,
+    history: Box<dyn HistoryBackend<STATE>>,
+    base_path: String,
 }
 
 impl<STATE> Default for RouteService<STATE>
@@ -46,99 +149,107 @@ where
     }
 }
 
-impl<T> RouteService<T> {
-    /// Creates the route service.
-    pub fn new() -> RouteService<T> {
-        let (history, location) = cfg_match! {
-            feature = "std_web" => ({
-                (
-                    window().history(),
-                    window().location().expect("browser does not support location API")
-                )
-            }),
-            feature = "web_sys" => ({
-                let window = web_sys::window().unwrap();
-                (
-                    window.history().expect("browser does not support history API"),
-                    window.location()
-                )
-            }),
-        };
+impl<STATE> RouteService<STATE>
+where
+    STATE: RouteState,
+{
+    /// Creates the route service, using the backend set by `set_default_history`,
+    /// and the base path set by `set_default_base_path`.
+    ///
+    /// If no backend was set, a `BrowserHistory` is used, with the mode set by
+    /// `set_default_routing_mode`.
+    ///
+    /// # Panics
+    /// Panics if the backend set by `set_default_history` stores a different `STATE` type.
+    pub fn new() -> RouteService<STATE> {
+        let history = DEFAULT_HISTORY.with(|default_history| {
+            default_history
+                .borrow()
+                .as_ref()
+                .map(|(state_type, make_history)| {
+                    let make_history = make_history
+                        .downcast_ref::<MakeHistory<STATE>>()
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "The default history stores `{}` states, so it can't be used by a \
+                                 `RouteService<{}>`.",
+                                state_type,
+                                type_name::<STATE>()
+                            )
+                        });
+                    make_history()
+                })
+        });
+        RouteService {
+            history: history
+                .unwrap_or_else(|| Box::new(BrowserHistory::new(default_routing_mode()))),
+            base_path: default_base_path(),
+        }
+    }
+
+    /// Creates the route service, storing the route in the browser's url as specified by the mode.
+    pub fn with_mode(mode: RoutingMode) -> RouteService<STATE> {
+        Self::with_history(BrowserHistory::new(mode))
+    }
 
+    /// Creates the route service, storing routes in the backend.
+    pub fn with_history(history: impl HistoryBackend<STATE> + 'static) -> RouteService<STATE> {
         RouteService {
-            history,
-            location,
-            event_listener: None,
-            phantom_data: PhantomData,
+            history: Box::new(history),
+            base_path: String::new(),
         }
     }
 
-    #[inline]
-    fn get_route_from_location(location: &Location) -> String {
-        let path = location.pathname().unwrap();
-        let query = location.search().unwrap();
-        let fragment = location.hash().unwrap();
-        format_route_string(&path, &query, &fragment)
+    /// Sets the path that the app is served under, like `/portal`.
+    ///
+    /// The base path is removed from the start of the backend's routes before they are given out,
+    /// and is prepended to routes before they are given to the backend, so routes don't need to
+    /// include it. Routes that aren't under the base path are given out unchanged.
+    ///
+    /// In `RoutingMode::Hash`, the route is stored in the url's fragment, so the path that the page
+    /// is served under isn't part of the route, and a base path isn't needed.
+    /// If one is set anyway, it is prepended to the route in the fragment, like `/#/portal/users/5`.
+    pub fn with_base_path(mut self, base_path: &str) -> RouteService<STATE> {
+        self.base_path = normalize_base_path(base_path);
+        self
+    }
+
+    /// Gets the path that the app is served under.
+    ///
+    /// This is empty if the app is served from the root of its host.
+    pub fn base_path(&self) -> &str {
+        &self.base_path
     }
 
-    /// Gets the path name of the current url.
+    /// Gets the path name of the current route.
     pub fn get_path(&self) -> String {
-        self.location.pathname().unwrap()
+        split_route(&self.get_route().route).0.to_string()
     }
 
-    /// Gets the query string of the current url.
+    /// Gets the query string of the current route.
     pub fn get_query(&self) -> String {
-        self.location.search().unwrap()
+        split_route(&self.get_route().route).1.to_string()
     }
 
-    /// Gets the fragment of the current url.
+    /// Gets the fragment of the current route.
     pub fn get_fragment(&self) -> String {
-        self.location.hash().unwrap()
+        split_route(&self.get_route().route).2.to_string()
     }
-}
 
-impl<STATE> RouteService<STATE>
-where
-    STATE: RouteState,
-{
     /// Registers a callback to the route service.
     /// Callbacks will be called when the History API experiences a change such as
     /// popping a state off of its stack when the forward or back buttons are pressed.
+    ///
+    /// In `RoutingMode::Hash`, callbacks are instead called when the fragment of the url changes.
     pub fn register_callback(&mut self, callback: Callback<Route<STATE>>) {
-        let cb = move |event: PopStateEvent| {
-            let state_value: Value = event.state();
-            let state_string: String = cfg_match! {
-                feature = "std_web" => String::try_from(state_value).unwrap_or_default(),
-                feature = "web_sys" => state_value.as_string().unwrap_or_default(),
-            };
-            let state: STATE = serde_json::from_str(&state_string).unwrap_or_else(|_| {
-                log::error!("Could not deserialize state string");
-                STATE::default()
-            });
-
-            // Can't use the existing location, because this is a callback, and can't move it in
-            // here.
-            let location: Location = cfg_match! {
-                feature = "std_web" => window().location().unwrap(),
-                feature = "web_sys" => web_sys::window().unwrap().location(),
-            };
-            let route: String = Self::get_route_from_location(&location);
-
-            callback.emit(Route { route, state })
-        };
-
-        cfg_if! {
-            if #[cfg(feature = "std_web")] {
-                self.event_listener = Some(window().add_event_listener(move |event: PopStateEvent| {
-                    cb(event)
-                }));
-            } else if #[cfg(feature = "web_sys")] {
-                self.event_listener = Some(EventListener::new(web_sys::window().unwrap().as_ref(), "popstate", move |event| {
-                    let event: PopStateEvent = event.clone().dyn_into().unwrap();
-                    cb(event)
-                }));
-            }
-        };
+        let base_path = self.base_path.clone();
+        self.history
+            .listen(Callback::from(move |route: Route<STATE>| {
+                callback.emit(Route {
+                    route: route_without_base_path(&route.route, &base_path),
+                    state: route.state,
+                })
+            }))
     }
 
     /// Sets the browser's url bar to contain the provided route,
@@ -146,58 +257,23 @@ where
     ///
     /// The route should be a relative path that starts with a `/`.
     pub fn set_route(&mut self, route: &str, state: STATE) {
-        let state_string: String = serde_json::to_string(&state).unwrap_or_else(|_| {
-            log::error!("Could not serialize state string");
-            "".to_string()
-        });
-        cfg_match! {
-            feature = "std_web" => ({
-                self.history.push_state(state_string, "", Some(route));
-            }),
-            feature = "web_sys" => ({
-                let _ = self.history.push_state_with_url(&Value::from_str(&state_string), "", Some(route));
-            }),
-        };
+        self.history
+            .push(&route_under_base_path(route, &self.base_path), state)
     }
 
     /// Replaces the route with another one removing the most recent history event and
     /// creating another history event in its place.
     pub fn replace_route(&mut self, route: &str, state: STATE) {
-        let state_string: String = serde_json::to_string(&state).unwrap_or_else(|_| {
-            log::error!("Could not serialize state string");
-            "".to_string()
-        });
-        cfg_match! {
-            feature = "std_web" => ({
-                let _ = self.history.replace_state(state_string, "", Some(route));
-            }),
-            feature = "web_sys" => ({
-                let _ = self.history.replace_state_with_url(&Value::from_str(&state_string), "", Some(route));
-            }),
-        };
+        self.history
+            .replace(&route_under_base_path(route, &self.base_path), state)
     }
 
     /// Gets the concatenated path, query, and fragment.
     pub fn get_route(&self) -> Route<STATE> {
-        let route_string = Self::get_route_from_location(&self.location);
-        let state: STATE = get_state_string(&self.history)
-            .or_else(|| {
-                log::trace!("History state is empty");
-                None
-            })
-            .and_then(|state_string| -> Option<STATE> {
-                serde_json::from_str(&state_string)
-                    .ok()
-                    .or_else(|| {
-                        log::error!("Could not deserialize state string");
-                        None
-                    })
-                    .and_then(std::convert::identity) // flatten
-            })
-            .unwrap_or_default();
+        let route = self.history.current();
         Route {
-            route: route_string,
-            state,
+            route: route_without_base_path(&route.route, &self.base_path),
+            state: route.state,
         }
     }
 }
@@ -215,18 +291,155 @@ pub(crate) fn format_route_string(path: &str, query: &str, fragment: &str) -> St
     )
 }
 
-fn get_state(history: &History) -> Value {
-    cfg_match! {
-        feature = "std_web" => js!(
-            return @{history}.state;
-        ),
-        feature = "web_sys" => history.state().unwrap(),
+/// Gets the url to give to the History API for the route.
+fn url_for_route(route: &str, mode: RoutingMode) -> String {
+    match mode {
+        RoutingMode::History => route.to_string(),
+        RoutingMode::Hash => format!("#{}", route),
+    }
+}
+
+/// Makes the base path start with a `/` and not end with one, so it can be prepended to routes.
+///
+/// A base path of `/` becomes empty.
+fn normalize_base_path(base_path: &str) -> String {
+    match base_path.trim_end_matches('/') {
+        "" => String::new(),
+        base_path if base_path.starts_with('/') => base_path.to_string(),
+        base_path => format!("/{}", base_path),
+    }
+}
+
+/// Prepends the base path to the route.
+fn route_under_base_path(route: &str, base_path: &str) -> String {
+    format!("{}{}", base_path, route)
+}
+
+/// Removes the base path from the start of the route's path.
+///
+/// If the path isn't under the base path, the route is returned unchanged.
+fn route_without_base_path(route: &str, base_path: &str) -> String {
+    let (path, query, fragment) = split_route(route);
+    let path = match path.strip_prefix(base_path) {
+        Some("") => "/",
+        Some(rest) if rest.starts_with('/') => rest,
+        _ => path,
+    };
+    format_route_string(path, query, fragment)
+}
+
+/// Splits a route into its path, query, and fragment.
+fn split_route(route: &str) -> (&str, &str, &str) {
+    let (rest, fragment) = route.split_at(route.find('#').unwrap_or_else(|| route.len()));
+    let (path, query) = rest.split_at(rest.find('?').unwrap_or_else(|| rest.len()));
+    (path, query, fragment)
+}
+
+/// Gets the route stored in the fragment of a url, which starts with a `#`.
+///
+/// An empty fragment is treated as the root route, `/`.
+fn route_from_hash(hash: &str) -> String {
+    match hash.strip_prefix('#').unwrap_or(hash) {
+        "" => "/".to_string(),
+        route if route.starts_with('/') => route.to_string(),
+        route => format!("/{}", route),
     }
 }
 
-fn get_state_string(history: &History) -> Option<String> {
-    cfg_match! {
-        feature = "std_web" => get_state(history).try_into().ok(),
-        feature = "web_sys" => get_state(history).as_string(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn route_from_hash_adds_leading_slash() {
+        assert_eq!(route_from_hash(""), "/");
+        assert_eq!(route_from_hash("#"), "/");
+        assert_eq!(route_from_hash("#/users/5?tab=posts"), "/users/5?tab=posts");
+        assert_eq!(route_from_hash("#users/5"), "/users/5");
+    }
+
+    #[test]
+    fn route_is_split() {
+        assert_eq!(
+            split_route("/users/5?tab=posts#top"),
+            ("/users/5", "?tab=posts", "#top")
+        );
+        assert_eq!(split_route("/users/5#top?"), ("/users/5", "", "#top?"));
+    }
+
+    #[test]
+    fn route_service_uses_history_backend() {
+        let history = MemoryHistory::new("/users/5?tab=posts", ());
+        let mut route_service = RouteService::with_history(history.clone());
+        assert_eq!(route_service.get_path(), "/users/5");
+        assert_eq!(route_service.get_query(), "?tab=posts");
+        route_service.set_route("/about", ());
+        assert_eq!(history.current().route, "/about");
+        history.back();
+        assert_eq!(route_service.get_route().route, "/users/5?tab=posts");
+    }
+
+    // `RouteService::new` can fall back to the browser's history, which natively only links with
+    // `web_sys`.
+    #[cfg(feature = "web_sys")]
+    #[test]
+    fn route_service_uses_default_history() {
+        let history = MemoryHistory::new("/users/5", 5u32);
+        set_default_history(history.clone());
+        let mut route_service = RouteService::<u32>::new();
+        assert_eq!(route_service.get_route(), history.current());
+        route_service.set_route("/about", 6);
+        assert_eq!(history.current().route, "/about");
+    }
+
+    #[cfg(feature = "web_sys")]
+    #[test]
+    #[should_panic(expected = "stores `u32` states, so it can't be used by a `RouteService<()>`")]
+    fn route_service_with_other_state_than_default_history_panics() {
+        set_default_history(MemoryHistory::new("/", 5u32));
+        RouteService::<()>::new();
+    }
+
+    #[test]
+    fn base_path_is_normalized() {
+        assert_eq!(normalize_base_path("/"), "");
+        assert_eq!(normalize_base_path("/portal/"), "/portal");
+        assert_eq!(normalize_base_path("portal"), "/portal");
+    }
+
+    #[test]
+    fn base_path_is_stripped() {
+        assert_eq!(
+            route_without_base_path("/portal/users/5?tab=posts", "/portal"),
+            "/users/5?tab=posts"
+        );
+        assert_eq!(route_without_base_path("/portal#top", "/portal"), "/#top");
+        assert_eq!(route_without_base_path("/portals", "/portal"), "/portals");
+        assert_eq!(route_without_base_path("/users/5", ""), "/users/5");
+        assert_eq!(url_for_route("/users/5", RoutingMode::Hash), "#/users/5");
+    }
+
+    #[test]
+    fn route_service_uses_base_path_with_any_backend() {
+        let history = MemoryHistory::new("/portal/users/5?tab=posts", ());
+        let mut route_service =
+            RouteService::with_history(history.clone()).with_base_path("portal/");
+        assert_eq!(route_service.base_path(), "/portal");
+        assert_eq!(route_service.get_path(), "/users/5");
+        assert_eq!(route_service.get_route().route, "/users/5?tab=posts");
+
+        let visited = Rc::new(RefCell::new(vec![]));
+        let visited_clone = Rc::clone(&visited);
+        route_service.register_callback(Callback::from(move |route: Route| {
+            visited_clone.borrow_mut().push(route.route)
+        }));
+        route_service.set_route("/about", ());
+        assert_eq!(history.current().route, "/portal/about");
+        route_service.replace_route("/", ());
+        assert_eq!(history.current().route, "/portal/");
+        assert_eq!(route_service.get_path(), "/");
+        history.back();
+        assert_eq!(*visited.borrow(), vec!["/users/5?tab=posts"]);
     }
 }
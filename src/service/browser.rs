@@ -0,0 +1,232 @@
+//! History backend that uses the browser's History API.
+use super::{format_route_string, route_from_hash, url_for_route, HistoryBackend, RoutingMode};
+use crate::route::{Route, RouteState};
+use cfg_if::cfg_if;
+use cfg_match::cfg_match;
+use std::marker::PhantomData;
+use yew::callback::Callback;
+
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        use stdweb::{
+            js,
+            unstable::{TryFrom, TryInto},
+            web::{
+                event::{HashChangeEvent, PopStateEvent},
+                window, EventListenerHandle, History, IEventTarget, Location,
+            },
+            Value,
+        };
+    } else if #[cfg(feature = "web_sys")] {
+        use web_sys::{History, Location, PopStateEvent};
+        use gloo::events::EventListener;
+        use wasm_bindgen::{JsValue as Value, JsCast};
+    }
+}
+
+/// Stores routes in the browser's url bar and History API, and responds to browser events
+/// when users press 'forward' or 'back'.
+///
+/// This is the backend used by `RouteService` unless another one is specified.
+#[derive(Debug)]
+pub struct BrowserHistory<STATE = ()> {
+    history: History,
+    location: Location,
+    mode: RoutingMode,
+    #[cfg(feature = "std_web")]
+    event_listener: Option<EventListenerHandle>,
+    #[cfg(feature = "web_sys")]
+    event_listener: Option<EventListener>,
+    phantom_data: PhantomData<STATE>,
+}
+
+impl<STATE> BrowserHistory<STATE> {
+    /// Creates the backend, storing the route in the url as specified by the mode.
+    pub fn new(mode: RoutingMode) -> BrowserHistory<STATE> {
+        let (history, location) = cfg_match! {
+            feature = "std_web" => ({
+                (
+                    window().history(),
+                    window().location().expect("browser does not support location API")
+                )
+            }),
+            feature = "web_sys" => ({
+                let window = web_sys::window().unwrap();
+                (
+                    window.history().expect("browser does not support history API"),
+                    window.location()
+                )
+            }),
+        };
+
+        BrowserHistory {
+            history,
+            location,
+            mode,
+            event_listener: None,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Gets the mode that determines where in the url the route is stored.
+    pub fn mode(&self) -> RoutingMode {
+        self.mode
+    }
+
+    #[inline]
+    fn get_route_from_location(location: &Location, mode: RoutingMode) -> String {
+        match mode {
+            RoutingMode::History => {
+                let path = location.pathname().unwrap();
+                let query = location.search().unwrap();
+                let fragment = location.hash().unwrap();
+                format_route_string(&path, &query, &fragment)
+            }
+            RoutingMode::Hash => route_from_hash(&location.hash().unwrap()),
+        }
+    }
+}
+
+impl<STATE> HistoryBackend<STATE> for BrowserHistory<STATE>
+where
+    STATE: RouteState,
+{
+    /// Sets the browser's url bar to contain the provided route,
+    /// and creates a history entry that can be navigated via the forward and back buttons.
+    fn push(&mut self, route: &str, state: STATE) {
+        let state_string: String = serde_json::to_string(&state).unwrap_or_else(|_| {
+            log::error!("Could not serialize state string");
+            "".to_string()
+        });
+        let url = url_for_route(route, self.mode);
+        cfg_match! {
+            feature = "std_web" => ({
+                self.history.push_state(state_string, "", Some(&url));
+            }),
+            feature = "web_sys" => ({
+                let _ = self.history.push_state_with_url(&Value::from_str(&state_string), "", Some(&url));
+            }),
+        };
+    }
+
+    fn replace(&mut self, route: &str, state: STATE) {
+        let state_string: String = serde_json::to_string(&state).unwrap_or_else(|_| {
+            log::error!("Could not serialize state string");
+            "".to_string()
+        });
+        let url = url_for_route(route, self.mode);
+        cfg_match! {
+            feature = "std_web" => ({
+                let _ = self.history.replace_state(state_string, "", Some(&url));
+            }),
+            feature = "web_sys" => ({
+                let _ = self.history.replace_state_with_url(&Value::from_str(&state_string), "", Some(&url));
+            }),
+        };
+    }
+
+    fn current(&self) -> Route<STATE> {
+        let route_string = Self::get_route_from_location(&self.location, self.mode);
+        let state: STATE = get_state_string(&self.history)
+            .or_else(|| {
+                log::trace!("History state is empty");
+                None
+            })
+            .and_then(|state_string| -> Option<STATE> {
+                serde_json::from_str(&state_string)
+                    .ok()
+                    .or_else(|| {
+                        log::error!("Could not deserialize state string");
+                        None
+                    })
+                    .and_then(std::convert::identity) // flatten
+            })
+            .unwrap_or_default();
+        Route {
+            route: route_string,
+            state,
+        }
+    }
+
+    /// Registers a callback that is called when the History API experiences a change such as
+    /// popping a state off of its stack when the forward or back buttons are pressed,
+    /// replacing any callback registered before through this backend.
+    ///
+    /// In `RoutingMode::Hash`, the callback is instead called when the fragment of the url changes.
+    fn listen(&mut self, callback: Callback<Route<STATE>>) {
+        let mode = self.mode;
+        let cb = move |state_value: Value| {
+            let state_string: String = cfg_match! {
+                feature = "std_web" => String::try_from(state_value).unwrap_or_default(),
+                feature = "web_sys" => state_value.as_string().unwrap_or_default(),
+            };
+            let state: STATE = serde_json::from_str(&state_string).unwrap_or_else(|_| {
+                log::error!("Could not deserialize state string");
+                STATE::default()
+            });
+
+            // Can't use the existing location, because this is a callback, and can't move it in
+            // here.
+            let location: Location = cfg_match! {
+                feature = "std_web" => window().location().unwrap(),
+                feature = "web_sys" => web_sys::window().unwrap().location(),
+            };
+            let route: String = Self::get_route_from_location(&location, mode);
+
+            callback.emit(Route { route, state })
+        };
+
+        cfg_if! {
+            if #[cfg(feature = "std_web")] {
+                // Unlike gloo's listeners, stdweb's aren't removed when their handle is dropped.
+                if let Some(event_listener) = self.event_listener.take() {
+                    event_listener.remove();
+                }
+                self.event_listener = Some(match mode {
+                    RoutingMode::History => window().add_event_listener(move |event: PopStateEvent| {
+                        cb(event.state())
+                    }),
+                    RoutingMode::Hash => window().add_event_listener(move |_: HashChangeEvent| {
+                        cb(get_state(&window().history()))
+                    }),
+                });
+            } else if #[cfg(feature = "web_sys")] {
+                self.event_listener = Some(match mode {
+                    RoutingMode::History => EventListener::new(web_sys::window().unwrap().as_ref(), "popstate", move |event| {
+                        let event: PopStateEvent = event.clone().dyn_into().unwrap();
+                        cb(event.state())
+                    }),
+                    RoutingMode::Hash => EventListener::new(web_sys::window().unwrap().as_ref(), "hashchange", move |_| {
+                        let history = web_sys::window().unwrap().history().unwrap();
+                        cb(get_state(&history))
+                    }),
+                });
+            }
+        };
+    }
+}
+
+#[cfg(feature = "std_web")]
+impl<STATE> Drop for BrowserHistory<STATE> {
+    fn drop(&mut self) {
+        if let Some(event_listener) = self.event_listener.take() {
+            event_listener.remove();
+        }
+    }
+}
+
+fn get_state(history: &History) -> Value {
+    cfg_match! {
+        feature = "std_web" => js!(
+            return @{history}.state;
+        ),
+        feature = "web_sys" => history.state().unwrap(),
+    }
+}
+
+fn get_state_string(history: &History) -> Option<String> {
+    cfg_match! {
+        feature = "std_web" => get_state(history).try_into().ok(),
+        feature = "web_sys" => get_state(history).as_string(),
+    }
+}
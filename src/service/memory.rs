@@ -0,0 +1,236 @@
+//! History backend that keeps its entries in memory.
+use super::HistoryBackend;
+use crate::route::Route;
+use std::{cell::RefCell, fmt::Debug, rc::Rc};
+use yew::callback::Callback;
+
+/// Stores routes in a list of entries in memory, without using the browser.
+///
+/// This allows route services, and the agents and components that use them, to be used in native
+/// unit tests and outside of a browser.
+///
+/// Clones of a `MemoryHistory` share the same entries, so a clone can be given to a `RouteService`
+/// (or to `set_default_history`) while the original is used to navigate and inspect the history.
+/// Each clone is a separate backend with its own callback, like each `BrowserHistory` is.
+#[derive(Debug)]
+pub struct MemoryHistory<STATE = ()> {
+    inner: Rc<RefCell<Inner<STATE>>>,
+    /// Identifies the callback registered through this clone.
+    id: usize,
+}
+
+#[derive(Debug)]
+struct Inner<STATE> {
+    entries: Vec<Route<STATE>>,
+    index: usize,
+    /// The callbacks registered through each clone, by the clone's id.
+    listeners: Vec<(usize, Callback<Route<STATE>>)>,
+    next_id: usize,
+}
+
+impl<STATE> Clone for MemoryHistory<STATE> {
+    fn clone(&self) -> Self {
+        let id = {
+            let mut inner = self.inner.borrow_mut();
+            inner.next_id += 1;
+            inner.next_id
+        };
+        MemoryHistory {
+            inner: Rc::clone(&self.inner),
+            id,
+        }
+    }
+}
+
+impl<STATE> Drop for MemoryHistory<STATE> {
+    fn drop(&mut self) {
+        let id = self.id;
+        self.inner
+            .borrow_mut()
+            .listeners
+            .retain(|(listener_id, _)| *listener_id != id);
+    }
+}
+
+impl<STATE: Default> Default for MemoryHistory<STATE> {
+    fn default() -> Self {
+        MemoryHistory::new("/", STATE::default())
+    }
+}
+
+impl<STATE> MemoryHistory<STATE> {
+    /// Creates a history with a single entry.
+    pub fn new(route: &str, state: STATE) -> Self {
+        MemoryHistory {
+            inner: Rc::new(RefCell::new(Inner {
+                entries: vec![Route {
+                    route: route.to_string(),
+                    state,
+                }],
+                index: 0,
+                listeners: vec![],
+                next_id: 0,
+            })),
+            id: 0,
+        }
+    }
+
+    /// Gets the index of the current entry.
+    pub fn index(&self) -> usize {
+        self.inner.borrow().index
+    }
+}
+
+impl<STATE: Clone> MemoryHistory<STATE> {
+    /// Gets every entry, from oldest to newest.
+    pub fn entries(&self) -> Vec<Route<STATE>> {
+        self.inner.borrow().entries.clone()
+    }
+
+    /// Moves to the previous entry, as if the browser's back button was pressed.
+    pub fn back(&self) {
+        self.go(-1)
+    }
+
+    /// Moves to the next entry, as if the browser's forward button was pressed.
+    pub fn forward(&self) {
+        self.go(1)
+    }
+
+    /// Moves by `delta` entries, calling the registered callbacks with the new current route.
+    ///
+    /// Like `history.go()` in a browser, nothing happens if there is no entry at that position.
+    pub fn go(&self, delta: isize) {
+        let (route, listeners) = {
+            let mut inner = self.inner.borrow_mut();
+            let index = inner.index as isize + delta;
+            if delta == 0 || index < 0 || index as usize >= inner.entries.len() {
+                return;
+            }
+            inner.index = index as usize;
+            let listeners = inner
+                .listeners
+                .iter()
+                .map(|(_, listener)| listener.clone())
+                .collect::<Vec<_>>();
+            (inner.entries[inner.index].clone(), listeners)
+        };
+        // The entries aren't borrowed while the callbacks are called, so they can use the history.
+        for listener in listeners {
+            listener.emit(route.clone());
+        }
+    }
+}
+
+impl<STATE> HistoryBackend<STATE> for MemoryHistory<STATE>
+where
+    STATE: Clone + Debug + 'static,
+{
+    fn push(&mut self, route: &str, state: STATE) {
+        let mut inner = self.inner.borrow_mut();
+        let index = inner.index + 1;
+        inner.entries.truncate(index);
+        inner.entries.push(Route {
+            route: route.to_string(),
+            state,
+        });
+        inner.index = index;
+    }
+
+    fn replace(&mut self, route: &str, state: STATE) {
+        let mut inner = self.inner.borrow_mut();
+        let index = inner.index;
+        inner.entries[index] = Route {
+            route: route.to_string(),
+            state,
+        };
+    }
+
+    fn current(&self) -> Route<STATE> {
+        let inner = self.inner.borrow();
+        inner.entries[inner.index].clone()
+    }
+
+    /// Registers a callback that is called when `back`, `forward`, or `go` changes the current
+    /// entry of this history or any of its clones,
+    /// replacing any callback registered before through this clone.
+    fn listen(&mut self, callback: Callback<Route<STATE>>) {
+        let id = self.id;
+        let mut inner = self.inner.borrow_mut();
+        inner
+            .listeners
+            .retain(|(listener_id, _)| *listener_id != id);
+        inner.listeners.push((id, callback));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_discards_forward_entries() {
+        let mut history = MemoryHistory::<()>::default();
+        history.push("/a", ());
+        history.push("/b", ());
+        history.back();
+        assert_eq!(history.current().route, "/a");
+        history.push("/c", ());
+        let routes: Vec<String> = history
+            .entries()
+            .into_iter()
+            .map(|entry| entry.route)
+            .collect();
+        assert_eq!(routes, vec!["/", "/a", "/c"]);
+        assert_eq!(history.index(), 2);
+    }
+
+    #[test]
+    fn listening_replaces_callback_of_clone() {
+        let mut history = MemoryHistory::<()>::default();
+        history.push("/a", ());
+        let mut clone = history.clone();
+        let mut dropped = history.clone();
+        let calls = Rc::new(RefCell::new(vec![]));
+        let record = |name: &'static str| {
+            let calls = Rc::clone(&calls);
+            Callback::from(move |_| calls.borrow_mut().push(name))
+        };
+        history.listen(record("replaced"));
+        history.listen(record("history"));
+        clone.listen(record("clone"));
+        dropped.listen(record("dropped"));
+        drop(dropped);
+        history.back();
+        assert_eq!(*calls.borrow(), vec!["history", "clone"]);
+    }
+
+    #[test]
+    fn navigating_calls_listeners_of_clones() {
+        let history = MemoryHistory::new("/a", 1u32);
+        let mut clone = history.clone();
+        let visited = Rc::new(RefCell::new(vec![]));
+        let visited_clone = Rc::clone(&visited);
+        clone.listen(Callback::from(move |route: Route<u32>| {
+            visited_clone.borrow_mut().push(route)
+        }));
+        clone.push("/b", 2);
+        clone.replace("/c", 3);
+        history.back();
+        history.back();
+        history.forward();
+        assert_eq!(
+            *visited.borrow(),
+            vec![
+                Route {
+                    route: "/a".to_string(),
+                    state: 1
+                },
+                Route {
+                    route: "/c".to_string(),
+                    state: 3
+                },
+            ]
+        );
+    }
+}
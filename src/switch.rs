@@ -1,6 +1,14 @@
 //! Parses routes into enums or structs.
-use crate::route::Route;
-use std::fmt::Write;
+use crate::{matcher::TrailingSlash, route::Route};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use std::{borrow::Cow, fmt::Write};
+
+mod explain;
+mod routes;
+pub use self::{
+    explain::{Explanation, Outcome, VariantExplanation},
+    routes::{route_manifest, CaptureInfo, RouteInfo},
+};
 
 /// Alias to Switch.
 ///
@@ -50,12 +58,24 @@ pub type Routable = Switch;
 pub trait Switch: Sized {
     /// Based on a route, possibly produce an itself.
     fn switch<STATE>(route: Route<STATE>) -> Option<Self> {
-        Self::from_route_part(route.route, Some(route.state)).0
+        Self::from_route_part_ref(&route.route, Some(route.state)).0
     }
 
     /// Get self from a part of the state
     fn from_route_part<STATE>(part: String, state: Option<STATE>) -> (Option<Self>, Option<STATE>);
 
+    /// Get self from a part of the state, borrowing the part instead of taking ownership of it.
+    ///
+    /// The derive macro implements this, matching against slices of the route, so that nested
+    /// implementors don't need to copy the route at every level.
+    /// By default, this copies the part and defers to `from_route_part`.
+    fn from_route_part_ref<STATE>(
+        part: &str,
+        state: Option<STATE>,
+    ) -> (Option<Self>, Option<STATE>) {
+        Self::from_route_part(part.to_string(), state)
+    }
+
     /// Build part of a route from itself.
     fn build_route_section<STATE>(self, route: &mut String) -> Option<STATE>;
 
@@ -70,6 +90,54 @@ pub trait Switch: Sized {
     fn key_not_available() -> Option<Self> {
         None
     }
+
+    /// The policy for trailing slashes that the implementor's matchers were created with.
+    ///
+    /// `trailing_slash_redirect` uses this to decide if a route should be redirected to the route
+    /// built from the item that was switched from it.
+    fn trailing_slash() -> TrailingSlash {
+        TrailingSlash::Strict
+    }
+
+    /// Explains how the route was matched against each variant, and why the variants that didn't
+    /// match failed to.
+    ///
+    /// The derive macro implements this to report how far each variant's matcher got into the
+    /// route, the token that failed, and any field that couldn't be created from its capture.
+    /// It is intended for debugging - the `Router` logs it in debug builds when no route matched.
+    fn explain(route: &str) -> Explanation {
+        let outcome = match Self::from_route_part_ref::<()>(route, None).0 {
+            Some(_) => Outcome::Matched,
+            None => Outcome::Rejected,
+        };
+        Explanation {
+            route: route.to_string(),
+            variants: vec![VariantExplanation {
+                name: std::any::type_name::<Self>(),
+                outcome,
+            }],
+        }
+    }
+
+    /// Describes the route matcher of each variant, in the order that they are tried in.
+    ///
+    /// The derive macro implements this, so tooling can list the routes of an app at build time,
+    /// without keeping a separate list that can drift from the type.
+    /// Implementors that weren't derived don't describe any routes.
+    fn routes() -> &'static [RouteInfo] {
+        &[]
+    }
+
+    /// Gets every route matched by the variants that don't have any captures, in the order that
+    /// the variants are tried in.
+    ///
+    /// This is intended for generating a `sitemap.xml` or a list of pages to pre-render.
+    fn static_routes() -> Vec<&'static str> {
+        Self::routes()
+            .iter()
+            .flat_map(|route| route.urls.iter().cloned())
+            .collect()
+    }
 }
 
 /// Wrapper that requires that an implementor of Switch must start with a `/`.
@@ -82,9 +150,15 @@ pub trait Switch: Sized {
 pub struct LeadingSlash<T>(pub T);
 impl<U: Switch> Switch for LeadingSlash<U> {
     fn from_route_part<STATE>(part: String, state: Option<STATE>) -> (Option<Self>, Option<STATE>) {
+        Self::from_route_part_ref(&part, state)
+    }
+
+    fn from_route_part_ref<STATE>(
+        part: &str,
+        state: Option<STATE>,
+    ) -> (Option<Self>, Option<STATE>) {
         if part.starts_with('/') {
-            let part = part[1..].to_string();
-            let (inner, state) = U::from_route_part(part, state);
+            let (inner, state) = U::from_route_part_ref(&part[1..], state);
             (inner.map(LeadingSlash), state)
         } else {
             (None, None)
@@ -104,7 +178,14 @@ pub struct Permissive<U>(pub Option<U>);
 impl<U: Switch> Switch for Permissive<U> {
     /// Option is very permissive in what is allowed.
     fn from_route_part<STATE>(part: String, state: Option<STATE>) -> (Option<Self>, Option<STATE>) {
-        let (inner, inner_state) = U::from_route_part(part, state);
+        Self::from_route_part_ref(&part, state)
+    }
+
+    fn from_route_part_ref<STATE>(
+        part: &str,
+        state: Option<STATE>,
+    ) -> (Option<Self>, Option<STATE>) {
+        let (inner, inner_state) = U::from_route_part_ref(part, state);
         if inner.is_some() {
             (Some(Permissive(inner)), inner_state)
         } else {
@@ -135,16 +216,22 @@ impl<U: Switch> Switch for Permissive<U> {
 pub struct AllowMissing<U: std::fmt::Debug>(pub Option<U>);
 impl<U: Switch + std::fmt::Debug> Switch for AllowMissing<U> {
     fn from_route_part<STATE>(part: String, state: Option<STATE>) -> (Option<Self>, Option<STATE>) {
-        let route = part.clone();
-        let (inner, inner_state) = U::from_route_part(part, state);
+        Self::from_route_part_ref(&part, state)
+    }
+
+    fn from_route_part_ref<STATE>(
+        part: &str,
+        state: Option<STATE>,
+    ) -> (Option<Self>, Option<STATE>) {
+        let (inner, inner_state) = U::from_route_part_ref(part, state);
 
         if inner.is_some() {
             (Some(AllowMissing(inner)), inner_state)
-        } else if &route == ""
-            || (&route).starts_with('/')
-            || (&route).starts_with('?')
-            || (&route).starts_with('&')
-            || (&route).starts_with('#')
+        } else if part == ""
+            || part.starts_with('/')
+            || part.starts_with('?')
+            || part.starts_with('&')
+            || part.starts_with('#')
         {
             (Some(AllowMissing(None)), inner_state)
         } else {
@@ -180,14 +267,96 @@ impl<SW: Switch, STATE: Default> From<SW> for Route<STATE> {
     }
 }
 
+/// Gets the route that a route should be redirected to, if the implementor's policy is
+/// `TrailingSlash::Redirect`.
+///
+/// This is the route built from the item that was switched from the route,
+/// if the two only differ by the `/` that ends their paths.
+/// The `Router` uses this to replace the route in the browser.
+pub fn trailing_slash_redirect<SW: Switch>(route: &str, switch: SW) -> Option<String> {
+    if SW::trailing_slash() != TrailingSlash::Redirect {
+        return None;
+    }
+    let canonical: Route = build_route_from_switch(switch);
+    if canonical.route != route
+        && without_trailing_slash(&canonical.route) == without_trailing_slash(route)
+    {
+        Some(canonical.route)
+    } else {
+        None
+    }
+}
+
+/// Removes the `/` that ends the path section of the route, if there is one.
+fn without_trailing_slash(route: &str) -> String {
+    let path_end = route
+        .find(|c| c == '?' || c == '#')
+        .unwrap_or_else(|| route.len());
+    let (path, rest) = route.split_at(path_end);
+    format!("{}{}", path.strip_suffix('/').unwrap_or(path), rest)
+}
+
+
+/// Characters that are percent-encoded when a value is written to a route.
+///
+/// This covers everything that would otherwise be mistaken for the start of a query, a fragment,
+/// a query parameter, or an escape sequence.
+/// `/` is left alone so that values captured over many sections keep their separators;
+/// the derive macro encodes it for captures of a single section.
+const ROUTE_VALUE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'<')
+    .add(b'=')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Replaces each `+` in a value captured by a query parameter with a space, which is how forms
+/// encode spaces in queries.
+///
+/// Code generated by the derive macro calls this before the value is percent-decoded,
+/// so `%2B` still decodes to `+`.
+pub fn decode_query_value(value: &str) -> Cow<'_, str> {
+    if value.contains('+') {
+        Cow::Owned(value.replace('+', " "))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
 
+/// Values are percent-decoded before being parsed, and percent-encoded when written,
+/// so arbitrary strings survive being written to a route and switched back.
+///
+/// Decoding happens here, instead of when matching, so that routes captured for nested `Switch`
+/// implementors are only decoded once.
 impl<T: std::str::FromStr + std::fmt::Display> Switch for T {
     fn from_route_part<U>(part: String, state: Option<U>) -> (Option<Self>, Option<U>) {
-        (::std::str::FromStr::from_str(&part).ok(), state)
+        Self::from_route_part_ref(&part, state)
+    }
+
+    fn from_route_part_ref<U>(part: &str, state: Option<U>) -> (Option<Self>, Option<U>) {
+        let value = percent_decode_str(part)
+            .decode_utf8()
+            .ok()
+            .and_then(|decoded| ::std::str::FromStr::from_str(&decoded).ok());
+        (value, state)
     }
 
     fn build_route_section<U>(self, route: &mut String) -> Option<U> {
-        write!(route, "{}", self).expect("Writing to string should never fail.");
+        let value = self.to_string();
+        write!(route, "{}", utf8_percent_encode(&value, ROUTE_VALUE))
+            .expect("Writing to string should never fail.");
         None
     }
 }
@@ -209,6 +378,33 @@ mod test {
         assert_eq!(s, Some("".to_string()))
     }
 
+    #[test]
+    fn leading_slash_from_borrowed_part() {
+        let (s, _state) = LeadingSlash::<usize>::from_route_part_ref::<()>("/42", None);
+        assert_eq!(s, Some(LeadingSlash(42)));
+        let (s, _state) = LeadingSlash::<usize>::from_route_part_ref::<()>("42", None);
+        assert_eq!(s, None);
+    }
+
+    #[test]
+    fn string_round_trips_through_percent_encoding() {
+        let value = "hello world/?a=b&c#d%20".to_string();
+        let mut route = String::new();
+        value.clone().build_route_section::<()>(&mut route);
+        assert_eq!(route, "hello%20world/%3Fa%3Db%26c%23d%2520");
+        let (decoded, _state) = String::from_route_part::<()>(route, None);
+        assert_eq!(decoded, Some(value))
+    }
+
+    #[test]
+    fn query_value_decodes_plus_to_space() {
+        assert_eq!(decode_query_value("a+b%2Bc"), "a b%2Bc");
+        assert!(matches!(
+            decode_query_value("a%20b"),
+            Cow::Borrowed("a%20b")
+        ));
+    }
+
     #[test]
     fn uuid_from_route() {
         let x = uuid::Uuid::switch::<()>(Route {
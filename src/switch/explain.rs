@@ -0,0 +1,88 @@
+//! Explanations of how routes are matched against `Switch` implementors.
+use crate::matcher::MatchFailure;
+use std::fmt;
+
+/// Explains how a route was matched against the variants of a `Switch` implementor.
+///
+/// This is produced by `Switch::explain`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Explanation {
+    /// The route that was explained.
+    pub route: String,
+    /// The variants that were tried, in the order that they were tried in.
+    ///
+    /// Variants after the one that matched wouldn't be tried, so they aren't included.
+    pub variants: Vec<VariantExplanation>,
+}
+
+/// Explains how a route was matched against a single variant.
+#[derive(Debug, PartialEq, Clone)]
+pub struct VariantExplanation {
+    /// The name of the variant, or of the type if it isn't an enum.
+    pub name: &'static str,
+    /// What happened when the route was matched against the variant.
+    pub outcome: Outcome,
+}
+
+/// What happened when a route was matched against a variant.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Outcome {
+    /// The route matched, and every field of the variant was created.
+    Matched,
+    /// The route didn't match the variant's route matcher string.
+    NoMatch(MatchFailure),
+    /// The route matched, but a field couldn't be created from what was captured for it.
+    FieldFailed {
+        /// The name of the field, or its index if it is unnamed.
+        field: &'static str,
+        /// The value that was captured for the field, or `None` if nothing was captured for it.
+        value: Option<String>,
+    },
+    /// The implementor didn't produce a value, without explaining why.
+    ///
+    /// This is the outcome for implementors that weren't derived.
+    Rejected,
+}
+
+impl Explanation {
+    /// Gets the variant that matched, if there was one.
+    pub fn matched(&self) -> Option<&VariantExplanation> {
+        self.variants
+            .iter()
+            .find(|variant| variant.outcome == Outcome::Matched)
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Route \"{}\"", self.route)?;
+        if self.variants.is_empty() {
+            return write!(f, " had no variants to be matched against");
+        }
+        for variant in &self.variants {
+            write!(f, "\n  {}: {}", variant.name, variant.outcome)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Matched => write!(f, "matched"),
+            Outcome::NoMatch(failure) => write!(f, "didn't match, {}", failure),
+            Outcome::FieldFailed {
+                field,
+                value: Some(value),
+            } => write!(
+                f,
+                "field `{}` couldn't be created from \"{}\"",
+                field, value
+            ),
+            Outcome::FieldFailed { field, value: None } => {
+                write!(f, "field `{}` wasn't captured", field)
+            }
+            Outcome::Rejected => write!(f, "rejected"),
+        }
+    }
+}
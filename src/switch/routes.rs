@@ -0,0 +1,61 @@
+//! Descriptions of the route matchers of `Switch` implementors.
+use crate::switch::Switch;
+use serde::Serialize;
+
+/// Describes the route matcher of a single variant.
+///
+/// These are produced by `Switch::routes`, and can be used to generate a sitemap or a list of
+/// pages to pre-render from the same type that the app is routed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RouteInfo {
+    /// The name of the variant, or of the type if it isn't an enum.
+    pub name: &'static str,
+    /// The route matcher string, joined from the variant's `#[to]`, `#[rest]`, and `#[end]`
+    /// attributes.
+    pub matcher: &'static str,
+    /// The literal sections of the matcher, in order.
+    ///
+    /// Every alternative of an alternation is listed, as are the sections of optional sections.
+    pub literals: &'static [&'static str],
+    /// The captures in the matcher, including those in its query, in order.
+    pub captures: &'static [CaptureInfo],
+    /// The keys of the query parameters that the matcher expects, in order.
+    pub query_keys: &'static [&'static str],
+    /// Every route that the matcher matches, if it doesn't have any captures.
+    ///
+    /// An alternation produces a route for each of its alternatives,
+    /// and an optional section produces routes both without and with it.
+    /// This is empty if the matcher has captures, because they can match any number of routes.
+    pub urls: &'static [&'static str],
+}
+
+impl RouteInfo {
+    /// Returns true if the matcher doesn't have any captures, so every route it matches is known.
+    pub fn is_static(&self) -> bool {
+        self.captures.is_empty()
+    }
+}
+
+/// Describes a capture within a route matcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CaptureInfo {
+    /// The name that the capture is stored under.
+    ///
+    /// The captures of unnamed fields are named by the index of their field.
+    pub name: &'static str,
+    /// The type of the field that the capture populates, as it is written in the source,
+    /// like `Option<u32>`.
+    ///
+    /// This is `None` if the capture doesn't populate a field.
+    #[serde(rename = "type")]
+    pub ty: Option<&'static str>,
+}
+
+/// Describes the route matcher of every variant of `SW` as a JSON array of `RouteInfo`s.
+///
+/// This allows services that aren't written in Rust to build links into an app from the same
+/// `#[to]` attributes that it is routed with, instead of keeping a copy of its routes.
+/// It is intended to be written to a file by a build script or a small binary.
+pub fn route_manifest<SW: Switch>() -> String {
+    serde_json::to_string_pretty(SW::routes()).expect("route infos can always be serialized")
+}
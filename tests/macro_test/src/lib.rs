@@ -176,6 +176,333 @@ mod tests {
         )
     }
 
+    #[test]
+    fn specificity_ranked_variants() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[switch(rank = "specificity")]
+        pub enum Test {
+            #[to = "/{*:rest}"]
+            NotFound(String),
+            #[to = "/user/{name}"]
+            User { name: String },
+            #[to = "/user/{id:u32}"]
+            UserId { id: u32 },
+            #[to = "/user/settings"]
+            Settings,
+        }
+        let switched = Test::switch(Route::new_no_state("/user/settings"));
+        assert_eq!(switched, Some(Test::Settings));
+        let switched = Test::switch(Route::new_no_state("/user/42"));
+        assert_eq!(switched, Some(Test::UserId { id: 42 }));
+        let switched = Test::switch(Route::new_no_state("/user/lorem"));
+        assert_eq!(
+            switched,
+            Some(Test::User {
+                name: "lorem".to_string()
+            })
+        );
+        let switched = Test::switch(Route::new_no_state("/about"));
+        assert_eq!(switched, Some(Test::NotFound("about".to_string())));
+    }
+
+    #[test]
+    fn nested_switch_from_borrowed_route() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Inner {
+            #[to = "/users/{id}/{name}"]
+            User { id: u32, name: String },
+        }
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Outer {
+            #[to = "/api{*:inner}"]
+            Api(Inner),
+        }
+        let route = "/api/users/1/lorem%20ipsum";
+        let (switched, _state) = Outer::from_route_part_ref::<()>(route, None);
+        assert_eq!(
+            switched,
+            Some(Outer::Api(Inner::User {
+                id: 1,
+                name: "lorem ipsum".to_string()
+            }))
+        );
+        let (owned, _state) = Outer::from_route_part::<()>(route.to_string(), None);
+        assert_eq!(owned, switched);
+    }
+
+    #[test]
+    fn explain_reports_each_variant() {
+        use yew_router::switch::{Outcome, VariantExplanation};
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/users/{id}"]
+            User { id: u32 },
+            #[to = "/posts/{}/{}"]
+            Post(String, u32),
+            #[to = "/about!"]
+            About,
+        }
+        let explanation = Test::explain("/users/lorem");
+        assert_eq!(
+            explanation.variants[0],
+            VariantExplanation {
+                name: "User",
+                outcome: Outcome::FieldFailed {
+                    field: "id",
+                    value: Some("lorem".to_string())
+                }
+            }
+        );
+        assert_eq!(explanation.variants.len(), 3);
+        assert_eq!(explanation.matched(), None);
+        match &explanation.variants[1].outcome {
+            Outcome::NoMatch(failure) => {
+                assert_eq!(failure.matched, 0);
+                assert_eq!(failure.remaining, "/users/lorem");
+            }
+            outcome => panic!("unexpected outcome: {:?}", outcome),
+        }
+
+        let explanation = Test::explain("/posts/lorem/1");
+        assert_eq!(explanation.variants.len(), 2);
+        assert_eq!(
+            explanation.matched().map(|variant| variant.name),
+            Some("Post")
+        );
+
+        let explanation = Test::explain("/about/");
+        match &explanation.variants[2].outcome {
+            Outcome::NoMatch(failure) => assert_eq!(failure.matched, "/about".len()),
+            outcome => panic!("unexpected outcome: {:?}", outcome),
+        }
+        assert_eq!(
+            explanation.to_string(),
+            "Route \"/about/\"\n  \
+             User: didn't match, Exact(\"/users/\") failed to match \"/about/\", after matching 0 bytes\n  \
+             Post: didn't match, Exact(\"/posts/\") failed to match \"/about/\", after matching 0 bytes\n  \
+             About: didn't match, End failed to match \"/\", after matching 6 bytes"
+        );
+    }
+
+    #[test]
+    fn trailing_slash_ignored() {
+        use yew_router::matcher::TrailingSlash;
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[switch(trailing_slash = "ignore")]
+        pub enum Test {
+            #[to = "/about!"]
+            About,
+            #[to = "/users/{id}/!"]
+            User { id: u32 },
+            #[to = "/search/?q={query}"]
+            Search { query: String },
+        }
+        assert_eq!(Test::trailing_slash(), TrailingSlash::Ignore);
+        for route in &["/about", "/about/"] {
+            assert_eq!(Test::switch(Route::new_no_state(route)), Some(Test::About));
+        }
+        for route in &["/users/1", "/users/1/"] {
+            assert_eq!(
+                Test::switch(Route::new_no_state(route)),
+                Some(Test::User { id: 1 })
+            );
+        }
+        for route in &["/search?q=lorem", "/search/?q=lorem"] {
+            assert_eq!(
+                Test::switch(Route::new_no_state(route)),
+                Some(Test::Search {
+                    query: "lorem".to_string()
+                })
+            );
+        }
+        let route: Route = Test::User { id: 1 }.into();
+        assert_eq!(route.route, "/users/1/");
+    }
+
+    #[test]
+    fn trailing_slash_strict_by_default() {
+        use yew_router::matcher::TrailingSlash;
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/about!"]
+            About,
+        }
+        assert_eq!(Test::trailing_slash(), TrailingSlash::Strict);
+        assert_eq!(Test::switch(Route::new_no_state("/about/")), None);
+    }
+
+    #[test]
+    fn case_sensitivity_set_by_attributes() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[switch(case_sensitive)]
+        pub enum Test {
+            #[to = "/share/aGk"]
+            Share,
+            #[to = "/share/aGK"]
+            ShareUpper,
+            #[to = "/about"]
+            #[switch(case_insensitive)]
+            About,
+        }
+        assert_eq!(
+            Test::switch(Route::new_no_state("/share/aGk")),
+            Some(Test::Share)
+        );
+        assert_eq!(
+            Test::switch(Route::new_no_state("/share/aGK")),
+            Some(Test::ShareUpper)
+        );
+        assert_eq!(Test::switch(Route::new_no_state("/share/AGK")), None);
+        assert_eq!(
+            Test::switch(Route::new_no_state("/ABOUT")),
+            Some(Test::About)
+        );
+    }
+
+    #[test]
+    fn case_insensitive_by_default() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/about"]
+            About,
+        }
+        assert_eq!(
+            Test::switch(Route::new_no_state("/ABOUT")),
+            Some(Test::About)
+        );
+    }
+
+    #[test]
+    fn routes_describe_each_variant() {
+        use yew_router::switch::{CaptureInfo, RouteInfo};
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/users/{id}?tab={tab}"]
+            User { id: u32, tab: Option<String> },
+            #[to = "/(posts|blog)[/archive]"]
+            Posts,
+            #[to = "/!"]
+            Home,
+        }
+        assert_eq!(
+            Test::routes()[0],
+            RouteInfo {
+                name: "User",
+                matcher: "/users/{id}?tab={tab}",
+                literals: &["/users/"],
+                captures: &[
+                    CaptureInfo {
+                        name: "id",
+                        ty: Some("u32")
+                    },
+                    CaptureInfo {
+                        name: "tab",
+                        ty: Some("Option<String>")
+                    }
+                ],
+                query_keys: &["tab"],
+                urls: &[],
+            }
+        );
+        assert_eq!(
+            Test::routes()[1].literals,
+            &["/", "posts", "blog", "/archive"]
+        );
+        assert_eq!(
+            Test::static_routes(),
+            vec!["/posts", "/blog", "/posts/archive", "/blog/archive", "/"]
+        );
+    }
+
+    #[test]
+    fn route_manifest_describes_captures() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/posts/{}/comments/{}"]
+            Comment(String, Option<u32>),
+            #[to = "/files"]
+            #[rest]
+            Files(String),
+        }
+        let manifest: serde_json::Value =
+            serde_json::from_str(&yew_router::switch::route_manifest::<Test>()).unwrap();
+        assert_eq!(
+            manifest[0]["captures"],
+            serde_json::json!([
+                { "name": "0", "type": "String" },
+                { "name": "1", "type": "Option<u32>" },
+            ])
+        );
+        assert_eq!(manifest[1]["matcher"], "/files{*}");
+        assert_eq!(manifest[1]["urls"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn variants_sharing_prefixes_are_tried_in_order() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/api/users/{id}"]
+            User { id: u32 },
+            #[to = "/api/{*:rest}"]
+            Api { rest: String },
+            #[to = "/about"]
+            About,
+            #[to = "{*:rest}"]
+            Fallback { rest: String },
+        }
+        let switched = Test::switch(Route::new_no_state("/api/users/1"));
+        assert_eq!(switched, Some(Test::User { id: 1 }));
+        let switched = Test::switch(Route::new_no_state("/API/users/lorem"));
+        assert_eq!(
+            switched,
+            Some(Test::Api {
+                rest: "users/lorem".to_string()
+            })
+        );
+        let switched = Test::switch(Route::new_no_state("/about"));
+        assert_eq!(switched, Some(Test::About));
+        let switched = Test::switch(Route::new_no_state("/lorem"));
+        assert_eq!(
+            switched,
+            Some(Test::Fallback {
+                rest: "/lorem".to_string()
+            })
+        );
+    }
+    #[test]
+    fn variants_are_dispatched_by_literals_after_captures() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[switch(trailing_slash = "ignore")]
+        pub enum Test {
+            #[to = "/user/{id}/posts/"]
+            Posts { id: u32 },
+            #[to = "/user/{name}/settings[/{section}]"]
+            Settings {
+                name: String,
+                section: Option<String>,
+            },
+            #[to = "/user/{name}"]
+            User { name: String },
+        }
+        let switched = Test::switch(Route::new_no_state("/user/1/posts/"));
+        assert_eq!(switched, Some(Test::Posts { id: 1 }));
+        let switched = Test::switch(Route::new_no_state("/USER/lorem/settings/privacy"));
+        assert_eq!(
+            switched,
+            Some(Test::Settings {
+                name: "lorem".to_string(),
+                section: Some("privacy".to_string())
+            })
+        );
+        let switched = Test::switch(Route::new_no_state("/user/lorem/posts"));
+        assert_eq!(
+            switched,
+            Some(Test::User {
+                name: "lorem".to_string()
+            })
+        );
+    }
+
     #[test]
     fn single_enum_variant_convert_usize() {
         #[derive(Debug, Switch, PartialEq, Clone)]
@@ -211,6 +538,335 @@ mod tests {
         assert_eq!(switched, Test::Variant(-42))
     }
 
+    #[test]
+    fn type_constrained_capture_disambiguates_variants() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/posts/{id:u32}"]
+            Id { id: u32 },
+            #[to = "/posts/{slug}"]
+            Slug { slug: String },
+        }
+        let route = Route::new_no_state("/posts/42");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Id { id: 42 });
+        let route = Route::new_no_state("/posts/hello-world");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Test::Slug {
+                slug: "hello-world".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn pattern_constrained_capture() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/variant/{item|[a-f0-9]+}"]
+            Variant(String),
+        }
+        let route = Route::new_no_state("/variant/c0ffee");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Variant("c0ffee".to_string()));
+        let route = Route::new_no_state("/variant/tea");
+        assert!(Test::switch(route).is_none());
+    }
+
+    #[test]
+    fn query_params_in_any_order() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/search?q={query}&page={page}"]
+            Search { query: String, page: u32 },
+        }
+        let route = Route::new_no_state("/search?page=2&utm_source=email&q=lorem");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Test::Search {
+                query: "lorem".to_string(),
+                page: 2
+            }
+        );
+        assert_eq!(Route::<()>::from(switched).route, "/search?q=lorem&page=2");
+    }
+
+    #[test]
+    fn optional_and_defaulted_query_params() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/search?q={query}?&page={page=1}"]
+            Search { query: Option<String>, page: u32 },
+        }
+        let route = Route::new_no_state("/search");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Test::Search {
+                query: None,
+                page: 1
+            }
+        );
+        assert_eq!(Route::<()>::from(switched).route, "/search?page=1");
+
+        let route = Route::new_no_state("/search?page=3&q=lorem");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Test::Search {
+                query: Some("lorem".to_string()),
+                page: 3
+            }
+        );
+        assert_eq!(Route::<()>::from(switched).route, "/search?q=lorem&page=3");
+    }
+
+    #[test]
+    fn repeated_query_params() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/posts?tag={tags}?&page={page=1}"]
+            Posts { tags: Vec<String>, page: u32 },
+        }
+        let route = Route::new_no_state("/posts?tag=lorem&page=2&tag=ipsum");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Test::Posts {
+                tags: vec!["lorem".to_string(), "ipsum".to_string()],
+                page: 2
+            }
+        );
+        assert_eq!(
+            Route::<()>::from(switched).route,
+            "/posts?tag=lorem&tag=ipsum&page=2"
+        );
+
+        let route = Route::new_no_state("/posts");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Test::Posts {
+                tags: vec![],
+                page: 1
+            }
+        );
+        assert_eq!(Route::<()>::from(switched).route, "/posts?page=1");
+    }
+
+    #[test]
+    fn empty_repeated_query_params_round_trip() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/tags?tag={tag}"]
+            Tags { tag: Vec<String> },
+        }
+        let route = Route::<()>::from(Test::Tags { tag: vec![] });
+        assert_eq!(route.route, "/tags");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Tags { tag: vec![] });
+    }
+
+    #[test]
+    fn repeated_query_params_in_tuple_struct() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/ids?id={}"]
+        pub struct Ids(Vec<u32>);
+        let route = Route::new_no_state("/ids?id=1&id=2&id=3");
+        let switched = Ids::switch(route).expect("should produce item");
+        assert_eq!(switched, Ids(vec![1, 2, 3]));
+        assert_eq!(Route::<()>::from(switched).route, "/ids?id=1&id=2&id=3");
+
+        let route = Route::new_no_state("/ids?id=1&id=lorem");
+        assert!(Ids::switch(route).is_none());
+    }
+
+    #[test]
+    fn captures_are_percent_decoded() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/search/{term}"]
+            Search { term: String },
+        }
+        let route = Route::new_no_state("/search/hello%20world");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Test::Search {
+                term: "hello world".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn constrained_captures_round_trip() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/c/{name|[a-z ]+}?q={query|[a-z ]+}"]
+        pub struct C {
+            name: String,
+            query: String,
+        }
+        let item = C {
+            name: "a b".to_string(),
+            query: "c d".to_string(),
+        };
+        let route = Route::<()>::from(item.clone());
+        assert_eq!(route.route, "/c/a%20b?q=c%20d");
+        assert_eq!(C::switch(route), Some(item));
+
+        let route = Route::new_no_state("/c/a%20b?q=c+d");
+        let switched = C::switch(route).expect("should produce item");
+        assert_eq!(switched.query, "c d");
+        let route = Route::new_no_state("/c/a%21b?q=c");
+        assert_eq!(C::switch(route), None);
+    }
+
+    #[test]
+    fn plus_in_query_captures_is_a_space() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/search/{term}?q={query}&tag={tags}"]
+            Search {
+                term: String,
+                query: Option<String>,
+                tags: Vec<String>,
+            },
+        }
+        let route = Route::new_no_state("/search/a+b?q=c+d%2Be&tag=f+g&tag=h");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Test::Search {
+                term: "a+b".to_string(),
+                query: Some("c d+e".to_string()),
+                tags: vec!["f g".to_string(), "h".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn arbitrary_strings_round_trip() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/search/{term}?q={query}#{fragment}"]
+            Search {
+                term: String,
+                query: String,
+                fragment: String,
+            },
+        }
+        let item = Test::Search {
+            term: "a/b?c#d e".to_string(),
+            query: "1+1=2 & 50%".to_string(),
+            fragment: "/path?#".to_string(),
+        };
+        let route = Route::<()>::from(item.clone());
+        assert_eq!(
+            route.route,
+            "/search/a%2Fb%3Fc%23d%20e?q=1%2B1%3D2%20%26%2050%25#%2Fpath%3F%23"
+        );
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, item);
+    }
+
+    #[test]
+    fn alternation() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/(posts|blog)/{id}"]
+            Post { id: u32 },
+        }
+        let route = Route::new_no_state("/posts/1");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Post { id: 1 });
+        let route = Route::new_no_state("/blog/1");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Post { id: 1 });
+        assert_eq!(Route::<()>::from(switched).route, "/posts/1");
+    }
+
+    #[test]
+    fn optional_section() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/users[/{id}]"]
+            Users { id: Option<u32> },
+        }
+        let route = Route::new_no_state("/users/42");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Users { id: Some(42) });
+        assert_eq!(Route::<()>::from(switched).route, "/users/42");
+
+        let route = Route::new_no_state("/users");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Users { id: None });
+        assert_eq!(Route::<()>::from(switched).route, "/users");
+    }
+
+    #[test]
+    fn optional_section_in_tuple_variant_keeps_field_order() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        pub enum Test {
+            #[to = "/posts[/page/{}]/comments/{}"]
+            Comments(Option<u32>, u32),
+        }
+        let route = Route::new_no_state("/posts/page/2/comments/5");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Comments(Some(2), 5));
+        assert_eq!(
+            Route::<()>::from(switched).route,
+            "/posts/page/2/comments/5"
+        );
+
+        let route = Route::new_no_state("/posts/comments/5");
+        let switched = Test::switch(route).expect("should produce item");
+        assert_eq!(switched, Test::Comments(None, 5));
+        assert_eq!(Route::<()>::from(switched).route, "/posts/comments/5");
+    }
+
+    #[test]
+    fn nested_optional_sections_in_struct() {
+        #[derive(Debug, Switch, PartialEq, Clone)]
+        #[to = "/archive[/{year}[/{month}]]"]
+        pub struct Archive {
+            year: Option<u32>,
+            month: Option<u8>,
+        }
+        let route = Route::new_no_state("/archive/2019/12");
+        let switched = Archive::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Archive {
+                year: Some(2019),
+                month: Some(12)
+            }
+        );
+        assert_eq!(Route::<()>::from(switched).route, "/archive/2019/12");
+
+        let route = Route::new_no_state("/archive/2019");
+        let switched = Archive::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Archive {
+                year: Some(2019),
+                month: None
+            }
+        );
+        assert_eq!(Route::<()>::from(switched).route, "/archive/2019");
+
+        let route = Route::new_no_state("/archive");
+        let switched = Archive::switch(route).expect("should produce item");
+        assert_eq!(
+            switched,
+            Archive {
+                year: None,
+                month: None
+            }
+        );
+    }
+
     #[test]
     fn single_enum_variant_missing_cap_produces_permissive_option_none() {
         #[derive(Debug, Switch, PartialEq, Clone)]
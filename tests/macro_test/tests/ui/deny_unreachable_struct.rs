@@ -0,0 +1,8 @@
+use yew_router::Switch;
+
+#[derive(Switch, Clone)]
+#[switch(deny_unreachable)]
+#[to = "/user"]
+struct User;
+
+fn main() {}
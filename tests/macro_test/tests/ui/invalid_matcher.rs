@@ -0,0 +1,9 @@
+use yew_router::Switch;
+
+#[derive(Switch, Clone)]
+enum AppRoute {
+    #[to = "/users/{id"]
+    User { id: u32 },
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yew_router::Switch;
+
+#[derive(Switch, Clone)]
+enum AppRoute {
+    #[to = "/caf\u{e9}/{id"]
+    Cafe { id: u32 },
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use yew_router::Switch;
+
+#[derive(Switch, Clone)]
+#[switch(deny_unreachable)]
+enum AppRoute {
+    #[to = "/{*:any}"]
+    Any(String),
+    #[to = "/about"]
+    About,
+}
+
+fn main() {}
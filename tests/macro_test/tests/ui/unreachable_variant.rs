@@ -0,0 +1,12 @@
+use yew_router::Switch;
+
+#[derive(Switch, Clone)]
+#[switch(deny_unreachable)]
+enum AppRoute {
+    #[to = "/user"]
+    User,
+    #[to = "/user/settings"]
+    Settings,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yew_router::Switch;
+
+#[derive(Switch, Clone)]
+enum AppRoute {
+    #[to = "/tags/{tags}"]
+    Tags { tags: Vec<String> },
+}
+
+fn main() {}